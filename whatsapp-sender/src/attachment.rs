@@ -0,0 +1,127 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::send_error::{SendError, SendErrorKind};
+
+const MEGABYTE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    Image,
+    Video,
+    Audio,
+    Document,
+}
+
+impl AttachmentKind {
+    // Limites da Cloud API, que também valem para o WhatsApp Web na prática
+    pub fn max_size(&self) -> u64 {
+        match self {
+            AttachmentKind::Image => 5 * MEGABYTE,
+            AttachmentKind::Video | AttachmentKind::Audio => 16 * MEGABYTE,
+            AttachmentKind::Document => 100 * MEGABYTE,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttachmentKind::Image => "image",
+            AttachmentKind::Video => "video",
+            AttachmentKind::Audio => "audio",
+            AttachmentKind::Document => "document",
+        }
+    }
+    
+    // Imagens e vídeos vão como mídia (com pré-visualização); o resto como documento
+    pub fn is_visual_media(&self) -> bool {
+        matches!(self, AttachmentKind::Image | AttachmentKind::Video)
+    }
+}
+
+// Arquivo a ser enviado, já validado
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub path: PathBuf,
+    pub kind: AttachmentKind,
+    pub mime_type: &'static str,
+    pub size: u64,
+}
+
+impl Attachment {
+    // Verifica existência, tipo e tamanho antes de qualquer tentativa de envio
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| Self::error(format!("Anexo não encontrado: {} ({})", path.display(), e)))?;
+        if !metadata.is_file() {
+            return Err(Self::error(format!("O anexo não é um arquivo: {}", path.display())));
+        }
+        
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let (kind, mime_type) = Self::detect_type(&extension).ok_or_else(|| {
+            Self::error(format!("Tipo de arquivo não suportado como anexo: {}", path.display()))
+        })?;
+        
+        let size = metadata.len();
+        if size == 0 {
+            return Err(Self::error(format!("O anexo está vazio: {}", path.display())));
+        }
+        if size > kind.max_size() {
+            return Err(Self::error(format!(
+                "O anexo {} tem {:.1} MB; o limite para este tipo é {} MB",
+                path.display(),
+                size as f64 / MEGABYTE as f64,
+                kind.max_size() / MEGABYTE
+            )));
+        }
+        
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind,
+            mime_type,
+            size,
+        })
+    }
+    
+    pub fn get_file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+    
+    pub fn get_size_megabytes(&self) -> f64 {
+        self.size as f64 / MEGABYTE as f64
+    }
+    
+    fn detect_type(extension: &str) -> Option<(AttachmentKind, &'static str)> {
+        let detected = match extension {
+            "jpg" | "jpeg" => (AttachmentKind::Image, "image/jpeg"),
+            "png" => (AttachmentKind::Image, "image/png"),
+            "mp4" => (AttachmentKind::Video, "video/mp4"),
+            "3gp" => (AttachmentKind::Video, "video/3gpp"),
+            "mp3" => (AttachmentKind::Audio, "audio/mpeg"),
+            "ogg" | "opus" => (AttachmentKind::Audio, "audio/ogg"),
+            "m4a" | "aac" => (AttachmentKind::Audio, "audio/aac"),
+            "amr" => (AttachmentKind::Audio, "audio/amr"),
+            "pdf" => (AttachmentKind::Document, "application/pdf"),
+            "txt" => (AttachmentKind::Document, "text/plain"),
+            "csv" => (AttachmentKind::Document, "text/csv"),
+            "doc" => (AttachmentKind::Document, "application/msword"),
+            "docx" => (AttachmentKind::Document, "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+            "xls" => (AttachmentKind::Document, "application/vnd.ms-excel"),
+            "xlsx" => (AttachmentKind::Document, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            "ppt" => (AttachmentKind::Document, "application/vnd.ms-powerpoint"),
+            "pptx" => (AttachmentKind::Document, "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+            "odt" => (AttachmentKind::Document, "application/vnd.oasis.opendocument.text"),
+            "ods" => (AttachmentKind::Document, "application/vnd.oasis.opendocument.spreadsheet"),
+            "zip" => (AttachmentKind::Document, "application/zip"),
+            _ => return None,
+        };
+        Some(detected)
+    }
+    
+    fn error(message: String) -> anyhow::Error {
+        SendError::new(SendErrorKind::Attachment, message).into()
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalStatus {
+    // Registrado antes do envio; se for o último estado, o app caiu durante a tentativa
+    Attempting,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    numero: String,
+    status: JournalStatus,
+    timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// Diário em disco das tentativas de envio de uma planilha.
+// Cada tentativa é gravada em uma linha JSON e sincronizada com o disco,
+// para que uma campanha interrompida possa ser retomada sem duplicar envios.
+pub struct CampaignJournal {
+    path: PathBuf,
+    file: File,
+    // Último estado conhecido de cada número normalizado
+    states: HashMap<String, JournalStatus>,
+}
+
+impl CampaignJournal {
+    // Caminho do diário associado a uma planilha
+    pub fn journal_path(workbook_path: &str) -> PathBuf {
+        let path = Path::new(workbook_path);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}_envio.journal", stem))
+    }
+    
+    pub fn exists(workbook_path: &str) -> bool {
+        Self::journal_path(workbook_path).exists()
+    }
+    
+    // Abre (ou cria) o diário da planilha carregando os estados já registrados
+    pub fn open(workbook_path: &str) -> Result<Self> {
+        let path = Self::journal_path(workbook_path);
+        let mut states = HashMap::new();
+        let mut torn_tail = false;
+        
+        if path.exists() {
+            let existing = fs::read(&path)
+                .with_context(|| format!("Erro ao ler o diário de envio: {}", path.display()))?;
+            
+            // Uma linha incompleta indica que o app caiu durante a gravação; ignorá-la
+            for line in String::from_utf8_lossy(&existing).lines() {
+                if let Ok(entry) = serde_json::from_str::<JournalEntry>(line) {
+                    states.insert(entry.numero, entry.status);
+                }
+            }
+            torn_tail = existing.last().is_some_and(|byte| *byte != b'\n');
+        }
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Erro ao criar o diário de envio: {}", path.display()))?;
+        
+        // Terminar a linha incompleta para que o próximo registro não seja colado a ela
+        if torn_tail {
+            file.write_all(b"\n")
+                .with_context(|| format!("Erro ao gravar no diário de envio: {}", path.display()))?;
+        }
+        
+        Ok(Self { path, file, states })
+    }
+    
+    // Descarta o diário da planilha para iniciar uma campanha do zero
+    pub fn reset(workbook_path: &str) -> Result<()> {
+        let path = Self::journal_path(workbook_path);
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Erro ao remover o diário de envio: {}", path.display()))?;
+        }
+        Ok(())
+    }
+    
+    pub fn is_sent(&self, numero: &str) -> bool {
+        self.states.get(numero) == Some(&JournalStatus::Sent)
+    }
+    
+    // Números cuja última tentativa ficou sem resultado
+    pub fn get_interrupted_count(&self) -> usize {
+        self.states.values().filter(|status| **status == JournalStatus::Attempting).count()
+    }
+    
+    pub fn record(&mut self, numero: &str, status: JournalStatus, error: Option<&str>) -> Result<()> {
+        let entry = JournalEntry {
+            numero: numero.to_string(),
+            status,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            error: error.map(|e| e.to_string()),
+        };
+        
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        
+        self.file.write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data())
+            .with_context(|| format!("Erro ao gravar no diário de envio: {}", self.path.display()))?;
+        
+        self.states.insert(entry.numero, status);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn workbook(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("whatsapp_sender_diario_{}_{}.xlsx", name, std::process::id()))
+            .to_string_lossy()
+            .to_string()
+    }
+    
+    #[test]
+    fn appends_after_torn_line_without_losing_entries() {
+        let workbook = workbook("linha_incompleta");
+        CampaignJournal::reset(&workbook).unwrap();
+        
+        let mut journal = CampaignJournal::open(&workbook).unwrap();
+        journal.record("+5511999998888", JournalStatus::Sent, None).unwrap();
+        drop(journal);
+        
+        // Simular uma queda no meio da gravação da linha seguinte
+        let mut file = OpenOptions::new().append(true).open(CampaignJournal::journal_path(&workbook)).unwrap();
+        file.write_all(br#"{"numero":"+5521988887777","sta"#).unwrap();
+        drop(file);
+        
+        let mut journal = CampaignJournal::open(&workbook).unwrap();
+        assert!(journal.is_sent("+5511999998888"));
+        assert!(!journal.is_sent("+5521988887777"));
+        journal.record("+5521988887777", JournalStatus::Sent, None).unwrap();
+        drop(journal);
+        
+        let journal = CampaignJournal::open(&workbook).unwrap();
+        assert!(journal.is_sent("+5511999998888"));
+        assert!(journal.is_sent("+5521988887777"));
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+}
@@ -0,0 +1,466 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::attachment::{Attachment, AttachmentKind};
+use crate::message_transport::{MessageTransport, QrCodeSink, SendReceipt};
+use crate::send_error::{SendError, SendErrorKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudApiConfig {
+    // URL base da Graph API; pode apontar para um servidor local em testes
+    pub base_url: String,
+    pub api_version: String,
+    pub phone_number_id: String,
+    // Não é salvo junto com as configurações
+    #[serde(skip)]
+    pub access_token: String,
+    // Nome do modelo aprovado; vazio envia texto livre
+    pub template_name: String,
+    pub template_language: String,
+    // Colunas da planilha usadas como {{1}}, {{2}}, ... separadas por vírgula
+    pub template_parameters: String,
+}
+
+impl Default for CloudApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: String::from("https://graph.facebook.com"),
+            api_version: String::from("v19.0"),
+            phone_number_id: String::new(),
+            access_token: String::new(),
+            template_name: String::new(),
+            template_language: String::from("pt_BR"),
+            template_parameters: String::new(),
+        }
+    }
+}
+
+impl CloudApiConfig {
+    pub fn get_parameter_columns(&self) -> Vec<String> {
+        self.template_parameters
+            .split(',')
+            .map(|column| column.trim().to_string())
+            .filter(|column| !column.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    #[serde(default)]
+    messages: Vec<MessageId>,
+}
+
+#[derive(Deserialize)]
+struct MessageId {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct MediaResponse {
+    id: String,
+}
+
+// Backend que envia pela WhatsApp Business Cloud API (endpoint /messages da Graph API)
+pub struct CloudApiTransport {
+    config: CloudApiConfig,
+    client: Option<reqwest::Client>,
+}
+
+impl CloudApiTransport {
+    pub fn new(config: &CloudApiConfig) -> Self {
+        Self {
+            config: config.clone(),
+            client: None,
+        }
+    }
+    
+    fn endpoint(&self, resource: &str) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.config.base_url.trim_end_matches('/'),
+            self.config.api_version.trim_matches('/'),
+            self.config.phone_number_id.trim(),
+            resource
+        )
+    }
+    
+    // A Cloud API espera apenas dígitos, com o código do país
+    fn recipient(numero: &str) -> String {
+        numero.chars().filter(|c| c.is_ascii_digit()).collect()
+    }
+    
+    fn client(&self) -> Result<&reqwest::Client> {
+        self.client.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cliente da Cloud API não inicializado"))
+    }
+    
+    // Monta os parâmetros do modelo a partir das colunas configuradas
+    fn template_parameters(&self, contact_data: &HashMap<String, String>) -> Result<Vec<Value>> {
+        self.config
+            .get_parameter_columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let value = contact_data
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(column))
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| anyhow::anyhow!(
+                        "Coluna '{}' não encontrada para o parâmetro {{{{{}}}}} do modelo",
+                        column,
+                        i + 1
+                    ))?;
+                Ok(json!({ "type": "text", "text": value }))
+            })
+            .collect()
+    }
+    
+    async fn post_message(&self, body: Value) -> Result<SendReceipt> {
+        let response = self.client()?
+            .post(self.endpoint("messages"))
+            .bearer_auth(&self.config.access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                let fallback = if e.is_timeout() { SendErrorKind::Timeout } else { SendErrorKind::Unknown };
+                SendError::from_error(e, fallback, "Falha ao enviar a requisição para a Cloud API")
+            })?;
+        
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(SendError::new(Self::classify_status(status, &text), format!("A Cloud API respondeu {}: {}", status, text)).into());
+        }
+        
+        let parsed: MessagesResponse = serde_json::from_str(&text)
+            .with_context(|| format!("Resposta inesperada da Cloud API: {}", text))?;
+        
+        Ok(SendReceipt {
+            message_id: parsed.messages.into_iter().next().map(|message| message.id),
+            ..Default::default()
+        })
+    }
+    
+    async fn upload_media(&self, file_path: &Path, mime_type: &str) -> Result<String> {
+        let bytes = tokio::fs::read(file_path).await
+            .with_context(|| format!("Erro ao ler o anexo: {}", file_path.display()))?;
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(file_name)
+            .mime_str(mime_type)?;
+        let form = reqwest::multipart::Form::new()
+            .text("messaging_product", "whatsapp")
+            .text("type", mime_type.to_string())
+            .part("file", part);
+        
+        let response = self.client()?
+            .post(self.endpoint("media"))
+            .bearer_auth(&self.config.access_token)
+            .multipart(form)
+            .send()
+            .await
+            .context("Falha ao enviar o anexo para a Cloud API")?;
+        
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(SendError::new(
+                SendErrorKind::Attachment,
+                format!("A Cloud API recusou o anexo ({}): {}", status, text),
+            )
+            .into());
+        }
+        
+        let media: MediaResponse = serde_json::from_str(&text)
+            .with_context(|| format!("Resposta inesperada da Cloud API: {}", text))?;
+        Ok(media.id)
+    }
+    
+    fn classify_status(status: reqwest::StatusCode, body: &str) -> SendErrorKind {
+        // 131026: destinatário não pode receber mensagens (número sem WhatsApp)
+        // 131030: número fora da lista de destinatários permitidos da conta de teste
+        if body.contains("131026") {
+            SendErrorKind::NotOnWhatsApp
+        } else if body.contains("131030") {
+            SendErrorKind::InvalidNumber
+        } else if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            SendErrorKind::Unauthorized
+        } else if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            SendErrorKind::Timeout
+        } else {
+            SendErrorKind::Unknown
+        }
+    }
+}
+
+#[async_trait]
+impl MessageTransport for CloudApiTransport {
+    fn name(&self) -> &str {
+        "WhatsApp Business Cloud API"
+    }
+    
+    async fn connect(&mut self) -> Result<()> {
+        if self.config.phone_number_id.trim().is_empty() {
+            return Err(anyhow::anyhow!("Informe o ID do número de telefone da Cloud API"));
+        }
+        if self.config.access_token.trim().is_empty() {
+            return Err(anyhow::anyhow!("Informe o token de acesso da Cloud API"));
+        }
+        
+        url::Url::parse(&self.endpoint("messages"))
+            .with_context(|| format!("URL base da Cloud API inválida: {}", self.config.base_url))?;
+        
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Falha ao criar o cliente HTTP")?;
+        
+        self.client = Some(client);
+        Ok(())
+    }
+    
+    async fn ensure_logged_in(&mut self, _timeout_seconds: u64, _on_qr_code: &QrCodeSink<'_>) -> Result<()> {
+        // A autenticação é feita pelo token em cada requisição
+        Ok(())
+    }
+    
+    async fn send_text(
+        &mut self,
+        numero: &str,
+        mensagem: &str,
+        contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let to = Self::recipient(numero);
+        
+        let body = if self.config.template_name.trim().is_empty() {
+            json!({
+                "messaging_product": "whatsapp",
+                "to": to,
+                "type": "text",
+                "text": { "body": mensagem },
+            })
+        } else {
+            let parameters = self.template_parameters(contact_data)?;
+            let components = if parameters.is_empty() {
+                json!([])
+            } else {
+                json!([{ "type": "body", "parameters": parameters }])
+            };
+            
+            json!({
+                "messaging_product": "whatsapp",
+                "to": to,
+                "type": "template",
+                "template": {
+                    "name": self.config.template_name.trim(),
+                    "language": { "code": self.config.template_language.trim() },
+                    "components": components,
+                },
+            })
+        };
+        
+        self.post_message(body).await
+    }
+    
+    async fn send_media(
+        &mut self,
+        numero: &str,
+        file_path: &Path,
+        caption: Option<&str>,
+        contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let attachment = Attachment::from_path(file_path)?;
+        let media_id = self.upload_media(&attachment.path, attachment.mime_type).await?;
+        
+        // Áudios não aceitam legenda e um modelo aprovado não pode ir como legenda:
+        // nesses casos o texto (ou o modelo) segue em uma mensagem própria, logo após o anexo
+        let caption = caption.filter(|caption| !caption.is_empty());
+        let uses_template = !self.config.template_name.trim().is_empty();
+        let separate_text = attachment.kind == AttachmentKind::Audio || uses_template;
+        
+        let mut media = json!({ "id": media_id });
+        if let Some(caption) = caption.filter(|_| !separate_text) {
+            media["caption"] = json!(caption);
+        }
+        if attachment.kind == AttachmentKind::Document {
+            media["filename"] = json!(attachment.get_file_name());
+        }
+        
+        let kind = attachment.kind.as_str();
+        let receipt = self.post_message(json!({
+            "messaging_product": "whatsapp",
+            "to": Self::recipient(numero),
+            "type": kind,
+            kind: media,
+        }))
+        .await?;
+        
+        let text = if uses_template { Some(caption.unwrap_or_default()) } else { caption.filter(|_| separate_text) };
+        match text {
+            // O anexo já foi entregue: a falha do texto não pode levar a uma nova tentativa do anexo
+            Some(text) => self.send_text(numero, text, contact_data).await.map_err(|e| {
+                SendError::new(
+                    SendErrorKind::Unknown,
+                    format!("O anexo foi enviado, mas a mensagem de texto falhou: {:#}", e),
+                )
+                .into()
+            }),
+            None => Ok(receipt),
+        }
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        self.client = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    
+    // Servidor local que responde uma requisição por resposta informada (status e corpo), em ordem,
+    // e devolve as requisições recebidas (linha inicial, cabeçalhos e corpo)
+    async fn stand_in_server(responses: &'static [(&'static str, &'static str)]) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut socket).await);
+                
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        
+        (base_url, handle)
+    }
+    
+    // Lê até o fim dos cabeçalhos e depois o corpo indicado em Content-Length (ou até o fim do corpo em partes)
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let Some(end) = text.find("\r\n\r\n") else {
+                continue;
+            };
+            let headers = text[..end].to_lowercase();
+            let length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok());
+            let complete = match length {
+                Some(length) => request.len() >= end + 4 + length,
+                None => !headers.contains("transfer-encoding: chunked") || text.ends_with("0\r\n\r\n"),
+            };
+            if complete {
+                break;
+            }
+        }
+        
+        String::from_utf8_lossy(&request).to_string()
+    }
+    
+    async fn connected_transport(base_url: String) -> CloudApiTransport {
+        let mut transport = CloudApiTransport::new(&CloudApiConfig {
+            base_url,
+            phone_number_id: String::from("123456"),
+            access_token: String::from("token-teste"),
+            ..Default::default()
+        });
+        transport.connect().await.unwrap();
+        transport
+    }
+    
+    #[tokio::test]
+    async fn sends_text_and_reads_message_id() {
+        let (base_url, server) = stand_in_server(&[("200 OK", r#"{"messages":[{"id":"wamid.teste"}]}"#)]).await;
+        let mut transport = connected_transport(base_url).await;
+        
+        let receipt = transport.send_text("+55 11 99999-8888", "Olá", &HashMap::new()).await.unwrap();
+        let request = server.await.unwrap().remove(0);
+        
+        assert_eq!(receipt.message_id.as_deref(), Some("wamid.teste"));
+        assert!(request.starts_with("POST /v19.0/123456/messages "), "{}", request);
+        assert!(request.to_lowercase().contains("authorization: bearer token-teste"), "{}", request);
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["to"], "5511999998888");
+        assert_eq!(body["text"]["body"], "Olá");
+    }
+    
+    #[tokio::test]
+    async fn classifies_rejected_token_as_unauthorized() {
+        let (base_url, server) = stand_in_server(&[("401 Unauthorized", r#"{"error":{"code":190}}"#)]).await;
+        let mut transport = connected_transport(base_url).await;
+        
+        let error = transport.send_text("5511999998888", "Olá", &HashMap::new()).await.unwrap_err();
+        server.await.unwrap();
+        
+        assert_eq!(SendError::classify(&error), SendErrorKind::Unauthorized);
+        assert!(!SendError::classify(&error).is_transient());
+    }
+    
+    #[tokio::test]
+    async fn classifies_recipient_without_whatsapp() {
+        let (base_url, server) = stand_in_server(&[("400 Bad Request", r#"{"error":{"code":131026}}"#)]).await;
+        let mut transport = connected_transport(base_url).await;
+        
+        let error = transport.send_text("5511999998888", "Olá", &HashMap::new()).await.unwrap_err();
+        server.await.unwrap();
+        
+        assert_eq!(SendError::classify(&error), SendErrorKind::NotOnWhatsApp);
+    }
+    
+    #[tokio::test]
+    async fn sends_audio_caption_as_separate_text_message() {
+        let (base_url, server) = stand_in_server(&[
+            ("200 OK", r#"{"id":"midia-1"}"#),
+            ("200 OK", r#"{"messages":[{"id":"wamid.audio"}]}"#),
+            ("200 OK", r#"{"messages":[{"id":"wamid.texto"}]}"#),
+        ])
+        .await;
+        let mut transport = connected_transport(base_url).await;
+        let audio = std::env::temp_dir().join(format!("whatsapp_sender_audio_{}.mp3", std::process::id()));
+        fs::write(&audio, b"ID3 audio de teste").unwrap();
+        
+        let receipt = transport.send_media("5511999998888", &audio, Some("Ouça o recado"), &HashMap::new()).await.unwrap();
+        let requests = server.await.unwrap();
+        fs::remove_file(&audio).unwrap();
+        
+        assert!(requests[0].starts_with("POST /v19.0/123456/media "), "{}", requests[0]);
+        let media: Value = serde_json::from_str(requests[1].split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(media["type"], "audio");
+        assert!(media["audio"].get("caption").is_none());
+        let text: Value = serde_json::from_str(requests[2].split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(text["text"]["body"], "Ouça o recado");
+        assert_eq!(receipt.message_id.as_deref(), Some("wamid.texto"));
+    }
+}
@@ -1,7 +1,19 @@
 use anyhow::{Context, Result};
-use calamine::{open_workbook, Reader, Xlsx, DataType};
+use calamine::Data;
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use regex::Regex;
+use rust_xlsxwriter::{Format, Workbook};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::attachment::Attachment;
+use crate::message_transport::{DeliveryStatus, SendReceipt};
+use crate::send_error::SendErrorKind;
+use crate::spreadsheet_reader::{self, SheetData, SourceFormat};
+
+// Quantas linhas preenchidas do topo da aba são examinadas em busca do cabeçalho
+const HEADER_SCAN_ROWS: usize = 30;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Contact {
@@ -10,16 +22,241 @@ pub struct Contact {
     // Campos adicionais opcionais
     pub email: Option<String>,
     pub empresa: Option<String>,
+    // Arquivo próprio do contato (coluna 'Anexo'), já resolvido
+    #[serde(default)]
+    pub anexo: Option<PathBuf>,
+    // Todas as colunas da linha, pelo nome do cabeçalho
+    #[serde(default)]
+    pub columns: HashMap<String, String>,
+}
+
+impl Contact {
+    // Dados usados nos {placeholders} da mensagem: todas as colunas (em minúsculas)
+    // mais os campos reconhecidos, que prevalecem sobre colunas de mesmo nome
+    pub fn to_template_data(&self) -> HashMap<String, String> {
+        let mut data: HashMap<String, String> = self
+            .columns
+            .iter()
+            .map(|(header, value)| (header.to_lowercase(), value.clone()))
+            .collect();
+        
+        data.insert(String::from("nome"), self.nome.clone());
+        data.insert(String::from("numero"), self.numero.clone());
+        if let Some(email) = &self.email {
+            data.insert(String::from("email"), email.clone());
+        }
+        if let Some(empresa) = &self.empresa {
+            data.insert(String::from("empresa"), empresa.clone());
+        }
+        
+        data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SendStatus {
+    Sent,
+    Failed,
+    // O número não tem conta no WhatsApp
+    NotOnWhatsApp,
+    // Já enviado em uma execução anterior da mesma campanha
+    Skipped,
+}
+
+impl SendStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SendStatus::Sent => "Enviado",
+            SendStatus::Failed => "Falha",
+            SendStatus::NotOnWhatsApp => "Sem WhatsApp",
+            SendStatus::Skipped => "Já enviado",
+        }
+    }
+}
+
+// Resultado do envio para um contato da planilha
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactResult {
+    pub contact: Contact,
+    pub status: SendStatus,
+    pub timestamp: String,
+    pub error: Option<String>,
+    // Classe final da falha, após as novas tentativas
+    pub error_kind: Option<SendErrorKind>,
+    pub attempts: u32,
+    // Mensagem exatamente como foi renderizada para o contato
+    pub message: String,
+    // Identificador devolvido pelo serviço de envio (ex.: Cloud API)
+    pub message_id: Option<String>,
+    // Estado confirmado pelo WhatsApp após o envio
+    pub delivery_status: Option<DeliveryStatus>,
+    // Arquivo efetivamente enviado (do contato ou o anexo comum da campanha)
+    pub attachment: Option<PathBuf>,
+}
+
+impl ContactResult {
+    pub fn new(contact: &Contact, status: SendStatus, error: Option<String>, message: String) -> Self {
+        Self {
+            contact: contact.clone(),
+            status,
+            timestamp: chrono::Local::now().format("%d/%m/%Y %H:%M:%S").to_string(),
+            error,
+            error_kind: None,
+            attempts: 0,
+            message,
+            message_id: None,
+            delivery_status: None,
+            attachment: None,
+        }
+    }
+    
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+    
+    pub fn with_error_kind(mut self, error_kind: SendErrorKind) -> Self {
+        self.error_kind = Some(error_kind);
+        self
+    }
+    
+    pub fn with_attachment(mut self, attachment: Option<&Path>) -> Self {
+        self.attachment = attachment.map(|path| path.to_path_buf());
+        self
+    }
+    
+    pub fn with_receipt(mut self, receipt: SendReceipt) -> Self {
+        self.message_id = receipt.message_id;
+        self.delivery_status = receipt.delivery_status;
+        self
+    }
+}
+
+// Nomes de cabeçalho aceitos para cada campo, separados por vírgula, e a escolha
+// manual de colunas feita pelo usuário quando a detecção automática falha
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub nome_aliases: String,
+    pub numero_aliases: String,
+    pub email_aliases: String,
+    pub empresa_aliases: String,
+    pub anexo_aliases: String,
+    // Cabeçalho escolhido manualmente; vazio usa os nomes acima
+    pub nome_column: String,
+    pub numero_column: String,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            nome_aliases: String::from("nome, nome completo, cliente, contato, name, full name"),
+            numero_aliases: String::from("numero, número, telefone, celular, whatsapp, fone, tel, phone, mobile"),
+            email_aliases: String::from("email, e-mail, mail"),
+            empresa_aliases: String::from("empresa, companhia, organização, company"),
+            anexo_aliases: String::from("anexo, arquivo, attachment"),
+            nome_column: String::new(),
+            numero_column: String::new(),
+        }
+    }
+}
+
+// Índices das colunas reconhecidas em uma linha de cabeçalho
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColumnIndexes {
+    pub nome: Option<usize>,
+    pub numero: Option<usize>,
+    pub email: Option<usize>,
+    pub empresa: Option<usize>,
+    pub anexo: Option<usize>,
+}
+
+impl ColumnMapping {
+    pub fn locate(&self, headers: &[String]) -> ColumnIndexes {
+        let headers: Vec<String> = headers.iter().map(|header| normalize_header(header)).collect();
+        
+        ColumnIndexes {
+            nome: Self::find(&headers, &self.nome_column, &self.nome_aliases),
+            numero: Self::find(&headers, &self.numero_column, &self.numero_aliases),
+            email: Self::find(&headers, "", &self.email_aliases),
+            empresa: Self::find(&headers, "", &self.empresa_aliases),
+            anexo: Self::find(&headers, "", &self.anexo_aliases),
+        }
+    }
+    
+    // A escolha manual tem prioridade; depois vale o primeiro cabeçalho que bate com algum nome
+    fn find(headers: &[String], column: &str, aliases: &str) -> Option<usize> {
+        let column = normalize_header(column);
+        if !column.is_empty() {
+            if let Some(idx) = headers.iter().position(|header| *header == column) {
+                return Some(idx);
+            }
+        }
+        
+        let aliases: Vec<String> = aliases
+            .split(',')
+            .map(normalize_header)
+            .filter(|alias| !alias.is_empty())
+            .collect();
+        headers.iter().position(|header| aliases.contains(header))
+    }
+}
+
+// Aba e linha de cabeçalho escolhidas pelo usuário; `None` detecta automaticamente
+#[derive(Debug, Clone, Default)]
+pub struct SheetSelection {
+    pub sheet_name: Option<String>,
+    // Número da linha como aparece na planilha (a primeira é 1)
+    pub header_row: Option<usize>,
+}
+
+// Colunas obrigatórias não encontradas, com os cabeçalhos e abas disponíveis para a escolha manual
+#[derive(Debug)]
+pub struct MissingColumnsError {
+    pub missing: Vec<&'static str>,
+    pub headers: Vec<String>,
+    pub sheet_names: Vec<String>,
+}
+
+impl std::fmt::Display for MissingColumnsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Coluna(s) {} não encontrada(s). Cabeçalhos da planilha: {}",
+            self.missing.join(" e "),
+            self.headers.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for MissingColumnsError {}
+
+// Célula que não era texto e foi convertida (ex.: telefone digitado como número)
+#[derive(Debug, Clone)]
+pub struct CoercedCell {
+    // Linha como aparece na planilha (a primeira é 1)
+    pub row: usize,
+    pub column: String,
+    pub original_type: &'static str,
+    pub value: String,
 }
 
 #[derive(Clone)]
 pub struct ExcelHandler {
     file_path: String,
+    format: SourceFormat,
     contacts: Vec<Contact>,
+    sheet_names: Vec<String>,
+    sheet_name: String,
+    // Linha do cabeçalho na planilha (a primeira é 1)
+    header_row: usize,
+    // Cabeçalhos como aparecem na planilha
+    headers: Vec<String>,
+    coerced_cells: Vec<CoercedCell>,
 }
 
 impl ExcelHandler {
-    pub fn new(file_path: &str) -> Result<Self> {
+    pub fn new(file_path: &str, mapping: &ColumnMapping, selection: &SheetSelection) -> Result<Self> {
         let path = Path::new(file_path);
         
         // Verificar se o arquivo existe
@@ -27,63 +264,89 @@ impl ExcelHandler {
             return Err(anyhow::anyhow!("O arquivo {} não existe", file_path));
         }
         
-        // Verificar se é um arquivo Excel
-        if !file_path.ends_with(".xlsx") && !file_path.ends_with(".xls") {
-            return Err(anyhow::anyhow!("O arquivo {} não é um arquivo Excel válido", file_path));
-        }
-        
-        // Abrir o arquivo Excel
-        let mut workbook: Xlsx<_> = open_workbook(file_path)
-            .with_context(|| format!("Erro ao abrir o arquivo Excel: {}", file_path))?;
+        // O formato (xlsx, xlsb, xls, ods ou csv) é identificado pelo conteúdo
+        let spreadsheet = spreadsheet_reader::read(path)?;
+        let format = spreadsheet.format;
+        let sheet_names = spreadsheet.get_sheet_names();
         
-        // Obter a primeira planilha
-        let sheet_name = workbook.sheet_names().get(0)
-            .ok_or_else(|| anyhow::anyhow!("O arquivo Excel não contém planilhas"))?
-            .clone();
-        
-        let sheet = workbook.worksheet_range(&sheet_name)
-            .with_context(|| "Erro ao acessar a planilha")?;
+        // Sem aba escolhida, vale a primeira que tiver as colunas obrigatórias
+        let candidates: Vec<&SheetData> = match &selection.sheet_name {
+            Some(name) => vec![spreadsheet.sheets.iter().find(|sheet| sheet.name == *name).ok_or_else(|| {
+                anyhow::anyhow!("A aba '{}' não existe. Abas disponíveis: {}", name, sheet_names.join(", "))
+            })?],
+            None => spreadsheet.sheets.iter().collect(),
+        };
         
-        // Verificar se a planilha está vazia
-        if sheet.is_empty() {
-            return Err(anyhow::anyhow!("A planilha está vazia"));
+        let mut found = None;
+        let mut first_attempt = None;
+        for sheet in &candidates {
+            let Some((header_idx, columns)) = Self::find_header_row(&sheet.rows, mapping, selection.header_row) else {
+                continue;
+            };
+            if columns.nome.is_some() && columns.numero.is_some() {
+                found = Some((*sheet, header_idx, columns));
+                break;
+            }
+            first_attempt.get_or_insert((*sheet, header_idx, columns));
         }
         
-        // Encontrar os índices das colunas necessárias
-        let mut nome_idx = None;
-        let mut numero_idx = None;
-        let mut email_idx = None;
-        let mut empresa_idx = None;
-        
-        if let Some(header_row) = sheet.rows().next() {
-            for (i, cell) in header_row.iter().enumerate() {
-                if let Some(value) = cell.get_string() {
-                    match value.to_lowercase().as_str() {
-                        "nome" => nome_idx = Some(i),
-                        "numero" => numero_idx = Some(i),
-                        "telefone" => numero_idx = Some(i),
-                        "email" => email_idx = Some(i),
-                        "empresa" => empresa_idx = Some(i),
-                        _ => {}
-                    }
+        // Verificar se as colunas obrigatórias existem; a interface usa o erro para pedir o mapeamento
+        let (sheet, header_idx, columns) = match found {
+            Some(found) => found,
+            None => {
+                let Some((sheet, header_idx, columns)) = first_attempt else {
+                    return Err(match selection.header_row {
+                        Some(row) => anyhow::anyhow!("A linha {} do cabeçalho não existe na planilha", row),
+                        None => anyhow::anyhow!("A planilha está vazia"),
+                    });
+                };
+                let mut missing = Vec::new();
+                if columns.nome.is_none() {
+                    missing.push("Nome");
+                }
+                if columns.numero.is_none() {
+                    missing.push("Telefone");
                 }
+                return Err(MissingColumnsError {
+                    missing,
+                    headers: row_texts(&sheet.rows[header_idx]).into_iter().filter(|header| !header.is_empty()).collect(),
+                    sheet_names,
+                }
+                .into());
             }
-        }
+        };
         
-        // Verificar se as colunas obrigatórias existem
-        let nome_idx = nome_idx.ok_or_else(|| anyhow::anyhow!("Coluna 'Nome' não encontrada"))?;
-        let numero_idx = numero_idx.ok_or_else(|| anyhow::anyhow!("Coluna 'Numero' ou 'Telefone' não encontrada"))?;
+        // Encontrar os índices das colunas pelos nomes reconhecidos
+        let header_names = row_texts(&sheet.rows[header_idx]);
+        let headers: Vec<String> = header_names.iter().map(|header| normalize_header(header)).collect();
+        let (nome_idx, numero_idx) = (columns.nome.unwrap(), columns.numero.unwrap());
+        
+        // Caminhos relativos do anexo partem da pasta da planilha
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+        let (email_idx, empresa_idx, anexo_idx) = (columns.email, columns.empresa, columns.anexo);
         
         // Extrair os contatos
         let mut contacts = Vec::new();
+        let mut coerced_cells = Vec::new();
         
-        for row in sheet.rows().skip(1) {
-            if row.len() <= nome_idx || row.len() <= numero_idx {
-                continue;
-            }
+        for (row_idx, row) in sheet.rows.iter().enumerate().skip(header_idx + 1) {
+            let mut row_coerced = Vec::new();
+            let mut read = |idx: Option<usize>| -> Option<String> {
+                let idx = idx?;
+                let (text, original_type) = cell_text(row.get(idx)?);
+                if let Some(original_type) = original_type.filter(|_| !text.is_empty()) {
+                    row_coerced.push(CoercedCell {
+                        row: row_idx + 1,
+                        column: header_names[idx].clone(),
+                        original_type,
+                        value: text.clone(),
+                    });
+                }
+                Some(text).filter(|text| !text.is_empty())
+            };
             
-            let nome = row[nome_idx].get_string().unwrap_or_default().to_string();
-            let numero = row[numero_idx].get_string().unwrap_or_default().to_string();
+            let nome = read(Some(nome_idx)).unwrap_or_default();
+            let numero = read(Some(numero_idx)).unwrap_or_default();
             
             // Pular linhas com dados incompletos
             if nome.is_empty() || numero.is_empty() {
@@ -91,27 +354,36 @@ impl ExcelHandler {
             }
             
             // Extrair campos opcionais
-            let email = email_idx.and_then(|idx| {
-                if idx < row.len() {
-                    row[idx].get_string().map(|s| s.to_string())
-                } else {
-                    None
-                }
-            });
+            let email = read(email_idx);
+            let empresa = read(empresa_idx);
+            let anexo = read(anexo_idx)
+                .map(|value| Self::resolve_attachment(&value, &headers, row, &base_dir));
             
-            let empresa = empresa_idx.and_then(|idx| {
-                if idx < row.len() {
-                    row[idx].get_string().map(|s| s.to_string())
-                } else {
-                    None
-                }
-            });
+            // As demais colunas também entram no relatório de conversões (ex.: valor e vencimento);
+            // as já lidas acima não são registradas de novo
+            let recognized = [Some(nome_idx), Some(numero_idx), email_idx, empresa_idx, anexo_idx];
+            let columns = header_names
+                .iter()
+                .enumerate()
+                .filter(|(_, header)| !header.is_empty())
+                .map(|(idx, header)| {
+                    let value = if recognized.contains(&Some(idx)) {
+                        row.get(idx).map(|cell| cell_text(cell).0).unwrap_or_default()
+                    } else {
+                        read(Some(idx)).unwrap_or_default()
+                    };
+                    (header.clone(), value)
+                })
+                .collect();
             
+            coerced_cells.extend(row_coerced);
             contacts.push(Contact {
                 nome,
                 numero,
                 email,
                 empresa,
+                anexo,
+                columns,
             });
         }
         
@@ -122,10 +394,100 @@ impl ExcelHandler {
         
         Ok(Self {
             file_path: file_path.to_string(),
+            format,
             contacts,
+            sheet_names,
+            sheet_name: sheet.name.clone(),
+            header_row: header_idx + 1,
+            headers: header_names.into_iter().filter(|header| !header.is_empty()).collect(),
+            coerced_cells,
         })
     }
     
+    // Linha do cabeçalho (índice a partir de 0) e colunas reconhecidas nela. Sem linha informada,
+    // usa a primeira que contém nome e telefone, pulando blocos de título acima da tabela;
+    // se nenhuma contiver, devolve a primeira linha não vazia para o mapeamento manual.
+    fn find_header_row(rows: &[Vec<Data>], mapping: &ColumnMapping, header_row: Option<usize>) -> Option<(usize, ColumnIndexes)> {
+        if let Some(header_row) = header_row {
+            let idx = header_row.checked_sub(1)?;
+            return rows.get(idx).map(|row| (idx, mapping.locate(&row_texts(row))));
+        }
+        
+        let mut first_non_empty = None;
+        // A busca começa na primeira linha preenchida, mesmo que a aba tenha linhas vazias no topo
+        let non_empty = rows.iter().enumerate().filter_map(|(idx, row)| {
+            let texts = row_texts(row);
+            (!texts.iter().all(|text| text.is_empty())).then_some((idx, texts))
+        });
+        for (idx, texts) in non_empty.take(HEADER_SCAN_ROWS) {
+            
+            let columns = mapping.locate(&texts);
+            if columns.nome.is_some() && columns.numero.is_some() {
+                return Some((idx, columns));
+            }
+            first_non_empty.get_or_insert((idx, columns));
+        }
+        
+        first_non_empty
+    }
+    
+    // Substitui {coluna} pelo valor da linha, permitindo padrões como boletos/{cpf}.pdf
+    fn resolve_attachment(pattern: &str, headers: &[String], row: &[Data], base_dir: &Path) -> PathBuf {
+        let placeholder = Regex::new(r"\{([^{}]+)\}").unwrap();
+        let resolved = placeholder.replace_all(pattern, |captures: &regex::Captures| {
+            let column = normalize_header(&captures[1]);
+            headers
+                .iter()
+                .position(|header| *header == column)
+                .and_then(|idx| row.get(idx))
+                .map(|cell| cell_text(cell).0)
+                .unwrap_or_else(|| captures[0].to_string())
+        });
+        
+        base_dir.join(resolved.as_ref())
+    }
+    
+    // Verifica os anexos de todos os contatos e descreve cada problema encontrado
+    pub fn validate_attachments(&self) -> Vec<String> {
+        self.contacts
+            .iter()
+            .filter_map(|contact| {
+                let anexo = contact.anexo.as_ref()?;
+                Attachment::from_path(anexo)
+                    .err()
+                    .map(|e| format!("{}: {:#}", contact.nome, e))
+            })
+            .collect()
+    }
+    
+    pub fn get_sheet_names(&self) -> &[String] {
+        &self.sheet_names
+    }
+    
+    pub fn get_sheet_name(&self) -> &str {
+        &self.sheet_name
+    }
+    
+    pub fn get_header_row(&self) -> usize {
+        self.header_row
+    }
+    
+    pub fn get_headers(&self) -> &[String] {
+        &self.headers
+    }
+    
+    pub fn get_coerced_cells(&self) -> &[CoercedCell] {
+        &self.coerced_cells
+    }
+    
+    pub fn get_format(&self) -> SourceFormat {
+        self.format
+    }
+    
+    pub fn get_file_path(&self) -> &str {
+        &self.file_path
+    }
+    
     pub fn get_contacts(&self) -> &[Contact] {
         &self.contacts
     }
@@ -160,22 +522,280 @@ impl ExcelHandler {
         preview
     }
     
-    pub fn save_results(&self, _results: &[(usize, String)], output_path: Option<&str>) -> Result<String> {
-        // Em uma implementação completa, salvaríamos os resultados em um novo arquivo Excel
-        // Como simplificação, apenas retornamos o caminho onde seria salvo
-        
+    pub fn save_results(&self, results: &[ContactResult], output_path: Option<&str>) -> Result<String> {
         let output_path = match output_path {
             Some(path) => path.to_string(),
             None => {
+                // O resultado é sempre .xlsx, mesmo quando a origem é .xls
                 let path = Path::new(&self.file_path);
-                let stem = path.file_stem().unwrap().to_string_lossy();
-                let ext = path.extension().unwrap().to_string_lossy();
-                format!("{}_resultados.{}", stem, ext)
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                path.with_file_name(format!("{}_resultados.xlsx", stem))
+                    .to_string_lossy()
+                    .to_string()
             }
         };
         
-        // Em uma implementação real, usaríamos rust_xlsxwriter para criar o arquivo
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name("Resultados")?;
+        
+        let header_format = Format::new().set_bold();
+        
+        // Colunas originais da planilha, na mesma ordem, seguidas das colunas do resultado
+        let status_headers = [
+            ("Status", 12.0),
+            ("Confirmação", 13.0),
+            ("Data/Hora", 20.0),
+            ("Tentativas", 11.0),
+            ("Tipo de Erro", 22.0),
+            ("Motivo do Erro", 40.0),
+            ("Mensagem Enviada", 60.0),
+            ("ID da Mensagem", 40.0),
+            ("Anexo Enviado", 40.0),
+        ];
+        let headers = self.headers
+            .iter()
+            .map(|header| (header.as_str(), 20.0))
+            .chain(status_headers);
+        
+        for (col, (header, width)) in headers.enumerate() {
+            worksheet.write_string_with_format(0, col as u16, header, &header_format)?;
+            worksheet.set_column_width(col as u16, width)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        
+        let first_status_col = self.headers.len() as u16;
+        for (i, result) in results.iter().enumerate() {
+            let row = i as u32 + 1;
+            let contact = &result.contact;
+            
+            for (col, header) in self.headers.iter().enumerate() {
+                let value = contact.columns.get(header).map(|value| value.as_str()).unwrap_or("");
+                worksheet.write_string(row, col as u16, value)?;
+            }
+            
+            let col = first_status_col;
+            worksheet.write_string(row, col, result.status.as_str())?;
+            worksheet.write_string(row, col + 1, result.delivery_status.map(|status| status.as_str()).unwrap_or(""))?;
+            worksheet.write_string(row, col + 2, result.timestamp.as_str())?;
+            worksheet.write_number(row, col + 3, result.attempts)?;
+            worksheet.write_string(row, col + 4, result.error_kind.map(|kind| kind.as_str()).unwrap_or(""))?;
+            worksheet.write_string(row, col + 5, result.error.as_deref().unwrap_or(""))?;
+            worksheet.write_string(row, col + 6, result.message.as_str())?;
+            worksheet.write_string(row, col + 7, result.message_id.as_deref().unwrap_or(""))?;
+            let anexo = result.attachment.as_ref().map(|path| path.to_string_lossy()).unwrap_or_default();
+            worksheet.write_string(row, col + 8, anexo.as_ref())?;
+        }
+        
+        workbook.save(&output_path)
+            .with_context(|| format!("Erro ao salvar o arquivo de resultados: {}", output_path))?;
         
         Ok(output_path)
     }
 }
+
+fn row_texts(row: &[Data]) -> Vec<String> {
+    row.iter().map(|cell| cell_text(cell).0).collect()
+}
+
+// Texto da célula como o usuário o vê na planilha. Quando a célula não era texto,
+// devolve também o tipo original, para que a conversão seja informada.
+fn cell_text(cell: &Data) -> (String, Option<&'static str>) {
+    match cell {
+        Data::Empty => (String::new(), None),
+        Data::String(value) => (value.trim().to_string(), None),
+        Data::Int(value) => (value.to_string(), Some("número")),
+        Data::Float(value) => (format_number(*value), Some("número")),
+        Data::Bool(value) => (String::from(if *value { "Sim" } else { "Não" }), Some("booleano")),
+        Data::DateTime(value) => match value.as_datetime() {
+            Some(datetime) if !value.is_duration() => (format_datetime(datetime), Some("data")),
+            _ => (format_number(value.as_f64()), Some("número")),
+        },
+        Data::DateTimeIso(value) => {
+            let text = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+                .map(format_datetime)
+                .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.format("%d/%m/%Y").to_string()))
+                .unwrap_or_else(|_| value.clone());
+            (text, Some("data"))
+        }
+        Data::DurationIso(value) => (value.clone(), Some("duração")),
+        Data::Error(_) => (String::new(), Some("erro")),
+    }
+}
+
+// Inteiros sem ".0" e sem notação científica (telefones e CPFs costumam vir como número)
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e18 {
+        format!("{}", value as i64)
+    } else {
+        // O Display de f64 nunca usa expoente
+        format!("{}", value)
+    }
+}
+
+fn format_datetime(datetime: NaiveDateTime) -> String {
+    if datetime.time().num_seconds_from_midnight() == 0 {
+        datetime.format("%d/%m/%Y").to_string()
+    } else {
+        datetime.format("%d/%m/%Y %H:%M").to_string()
+    }
+}
+
+// Forma usada para comparar cabeçalhos: minúsculas, sem acentos, sem pontuação e com
+// espaços simples ("Número do Celular" e "numero do celular" são iguais)
+pub fn normalize_header(header: &str) -> String {
+    let folded: String = header
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+    
+    #[test]
+    fn converts_float_phone_without_exponent_or_decimal() {
+        assert_eq!(cell_text(&Data::Float(5511999998888.0)), (String::from("5511999998888"), Some("número")));
+        assert_eq!(cell_text(&Data::Float(42.0)).0, "42");
+        assert_eq!(cell_text(&Data::Int(42)).0, "42");
+    }
+    
+    #[test]
+    fn keeps_fractional_part_of_numbers() {
+        assert_eq!(cell_text(&Data::Float(1234.56)).0, "1234.56");
+        assert_eq!(cell_text(&Data::Float(0.000015)).0, "0.000015");
+    }
+    
+    #[test]
+    fn formats_dates_with_and_without_time() {
+        // 45292 é 01/01/2024 no calendário do Excel; a fração é a hora do dia
+        let date = Data::DateTime(ExcelDateTime::new(45292.0, ExcelDateTimeType::DateTime, false));
+        let datetime = Data::DateTime(ExcelDateTime::new(45292.75, ExcelDateTimeType::DateTime, false));
+        
+        assert_eq!(cell_text(&date), (String::from("01/01/2024"), Some("data")));
+        assert_eq!(cell_text(&datetime).0, "01/01/2024 18:00");
+        assert_eq!(cell_text(&Data::DateTimeIso(String::from("2024-03-05"))).0, "05/03/2024");
+    }
+    
+    #[test]
+    fn converts_booleans_to_portuguese() {
+        assert_eq!(cell_text(&Data::Bool(true)), (String::from("Sim"), Some("booleano")));
+        assert_eq!(cell_text(&Data::Bool(false)).0, "Não");
+    }
+    
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+    
+    #[test]
+    fn matches_phone_aliases_ignoring_accents_and_case() {
+        let mapping = ColumnMapping::default();
+        
+        for phone in ["Número", "NÚMERO", "Celular", "WhatsApp", " Telefone "] {
+            let columns = mapping.locate(&headers(&["Nome Completo", phone]));
+            assert_eq!(columns.nome, Some(0), "{}", phone);
+            assert_eq!(columns.numero, Some(1), "{}", phone);
+        }
+        
+        assert_eq!(normalize_header("  Número do   Celular! "), "numero do celular");
+    }
+    
+    #[test]
+    fn manual_column_takes_priority_over_aliases() {
+        let mapping = ColumnMapping {
+            numero_column: String::from("Fone Comercial"),
+            ..Default::default()
+        };
+        
+        let columns = mapping.locate(&headers(&["Nome", "Telefone", "Fone Comercial"]));
+        assert_eq!(columns.numero, Some(2));
+        
+        // Sem a coluna escolhida na planilha, os nomes reconhecidos voltam a valer
+        let columns = mapping.locate(&headers(&["Nome", "Telefone"]));
+        assert_eq!(columns.numero, Some(1));
+    }
+    
+    fn row(cells: &[&str]) -> Vec<Data> {
+        cells.iter().map(|cell| if cell.is_empty() { Data::Empty } else { Data::String(cell.to_string()) }).collect()
+    }
+    
+    #[test]
+    fn finds_header_below_title_block() {
+        let rows = vec![
+            row(&["Relatório de cobrança"]),
+            row(&["Gerado em 01/01/2024", ""]),
+            row(&["Nome", "Celular", "Valor"]),
+            row(&["Ana", "11999998888", "10"]),
+        ];
+        
+        let (idx, columns) = ExcelHandler::find_header_row(&rows, &ColumnMapping::default(), None).unwrap();
+        assert_eq!(idx, 2);
+        assert_eq!((columns.nome, columns.numero), (Some(0), Some(1)));
+    }
+    
+    #[test]
+    fn skips_leading_empty_rows() {
+        let mut rows = vec![Vec::new(), row(&["", ""]), Vec::new()];
+        rows.push(row(&["Nome", "Telefone"]));
+        rows.push(row(&["Ana", "11999998888"]));
+        
+        let (idx, _) = ExcelHandler::find_header_row(&rows, &ColumnMapping::default(), None).unwrap();
+        assert_eq!(idx, 3);
+    }
+    
+    #[test]
+    fn uses_explicit_header_row_and_rejects_rows_past_the_end() {
+        let rows = vec![
+            row(&["Nome", "Telefone"]),
+            row(&["Cliente", "WhatsApp"]),
+        ];
+        let mapping = ColumnMapping::default();
+        
+        let (idx, columns) = ExcelHandler::find_header_row(&rows, &mapping, Some(2)).unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!((columns.nome, columns.numero), (Some(0), Some(1)));
+        
+        assert!(ExcelHandler::find_header_row(&rows, &mapping, Some(3)).is_none());
+        assert!(ExcelHandler::find_header_row(&rows, &mapping, Some(0)).is_none());
+    }
+    
+    #[test]
+    fn reports_conversions_in_template_only_columns_once() {
+        let path = std::env::temp_dir().join(format!("whatsapp_sender_conversoes_{}.xlsx", std::process::id()));
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        for (col, header) in ["Nome", "Telefone", "Valor"].iter().enumerate() {
+            worksheet.write_string(0, col as u16, *header).unwrap();
+        }
+        worksheet.write_string(1, 0, "Ana").unwrap();
+        worksheet.write_number(1, 1, 11999998888.0).unwrap();
+        worksheet.write_number(1, 2, 150.5).unwrap();
+        workbook.save(&path).unwrap();
+        
+        let handler = ExcelHandler::new(&path.to_string_lossy(), &ColumnMapping::default(), &SheetSelection::default()).unwrap();
+        std::fs::remove_file(&path).ok();
+        
+        let columns: Vec<(&str, &str)> = handler
+            .get_coerced_cells()
+            .iter()
+            .map(|cell| (cell.column.as_str(), cell.value.as_str()))
+            .collect();
+        assert_eq!(columns, vec![("Telefone", "11999998888"), ("Valor", "150.5")]);
+        assert_eq!(handler.get_contacts()[0].columns.get("Valor").map(String::as_str), Some("150.5"));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    // Não impede o envio, mas exige atenção (ex.: será preciso escanear o código QR)
+    Warning,
+    Failed,
+    // Não executada porque uma verificação anterior falhou
+    Skipped,
+}
+
+impl CheckStatus {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CheckStatus::Passed => "✔",
+            CheckStatus::Warning => "⚠",
+            CheckStatus::Failed => "✖",
+            CheckStatus::Skipped => "–",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+// Resultado da verificação feita antes de uma campanha
+#[derive(Debug, Clone, Default)]
+pub struct AvailabilityReport {
+    pub checks: Vec<HealthCheck>,
+}
+
+impl AvailabilityReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn add(&mut self, name: &str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(HealthCheck {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        });
+    }
+    
+    // Marca as verificações restantes como não executadas
+    pub fn skip(&mut self, names: &[&str]) {
+        for name in names {
+            self.add(name, CheckStatus::Skipped, "Não verificado");
+        }
+    }
+    
+    // Pronto para enviar quando nenhuma verificação falhou
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Failed)
+    }
+}
+
+impl fmt::Display for AvailabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{} {}: {}", check.status.symbol(), check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,129 @@
 use eframe::{egui, App, CreationContext};
 use egui::{Button, CentralPanel, Context, ScrollArea, TextEdit, TopBottomPanel, Ui};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::Duration;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use tokio::sync::watch;
+use tokio::time::Instant;
 
+mod attachment;
+mod campaign_journal;
+mod cloud_api;
 mod excel_handler;
+mod health_check;
 mod message_handler;
+mod message_transport;
+mod selector_profile;
+mod send_error;
+mod spreadsheet_reader;
+mod webdriver_process;
 mod whatsapp_automation;
 
-use excel_handler::ExcelHandler;
+use attachment::Attachment;
+use campaign_journal::{CampaignJournal, JournalStatus};
+use excel_handler::{ColumnMapping, Contact, ContactResult, ExcelHandler, MissingColumnsError, SendStatus, SheetSelection};
+use health_check::{AvailabilityReport, CheckStatus};
 use message_handler::MessageHandler;
+use message_transport::{
+    create_transport, create_whatsapp_automation, DeliveryStatus, MessageTransport, SendReceipt, TransportConfig, TransportKind,
+};
+use selector_profile::SelectorProfile;
+use send_error::{RetryPolicy, SendError, SendErrorKind};
+use whatsapp_automation::{BrowserKind, WhatsAppAutomation};
+
+// Tempo máximo para o usuário escanear o código QR
+const LOGIN_TIMEOUT_SECONDS: u64 = 120;
+
+// Chaves das configurações salvas entre execuções
+const TRANSPORT_KIND_KEY: &str = "transport_kind";
+const TRANSPORT_CONFIG_KEY: &str = "transport_config";
+const COLUMN_MAPPING_KEY: &str = "column_mapping";
+
+// Parâmetros de uma campanha, copiados da interface no início do envio
+#[derive(Clone)]
+struct CampaignSettings {
+    message_template: String,
+    // Arquivo enviado aos contatos sem anexo próprio, com a mensagem como legenda
+    attachment_path: Option<PathBuf>,
+    delay_seconds: u32,
+    retry_policy: RetryPolicy,
+}
+
+// Mensagem já personalizada para um contato, pronta para o envio
+struct OutgoingMessage<'a> {
+    contact: &'a Contact,
+    numero: &'a str,
+    mensagem: &'a str,
+    contact_data: &'a HashMap<String, String>,
+    attachment: Option<&'a Path>,
+}
+
+// Resultado de cada contato processado e, se a campanha parou antes do fim, o motivo
+struct CampaignOutcome {
+    results: Vec<ContactResult>,
+    aborted: Option<String>,
+}
+
+// Estado de execução controlado pela interface
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RunState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+// Eventos enviados pela thread de envio para a interface
+enum SendEvent {
+    Stage(String),
+    LoginRequired,
+    LoggedIn,
+    // Nova imagem PNG do código QR de login
+    QrCode(Vec<u8>),
+    Preflight(AvailabilityReport),
+    Paused,
+    Resumed,
+    Started { total: usize, skipped: usize },
+    ContactSent { index: usize, nome: String, delivery_status: Option<DeliveryStatus> },
+    ContactFailed { index: usize, nome: String, error: String },
+    Finished { results_path: Result<String, String> },
+    Aborted { reason: String },
+}
 
 struct WhatsAppSenderApp {
     excel_path: String,
+    column_mapping: ColumnMapping,
+    // Cabeçalhos oferecidos na escolha manual de colunas; vazio quando não é necessária
+    mapping_headers: Vec<String>,
+    // Abas do arquivo carregado; a escolha vazia usa a primeira aba com as colunas obrigatórias
+    sheet_names: Vec<String>,
+    selected_sheet: String,
+    // Linha do cabeçalho como aparece na planilha; 0 detecta automaticamente
+    header_row: u32,
     message_template: String,
+    attachment_path: String,
     delay_seconds: u32,
+    retry_policy: RetryPolicy,
+    transport_kind: TransportKind,
+    transport_config: TransportConfig,
     status_text: String,
     progress: f32,
     excel_handler: Option<ExcelHandler>,
     message_handler: MessageHandler,
     is_sending: bool,
+    is_paused: bool,
     sending_thread: Option<thread::JoinHandle<()>>,
+    run_control: Option<watch::Sender<RunState>>,
+    qr_code_texture: Option<egui::TextureHandle>,
+    availability_report: Option<AvailabilityReport>,
+    event_receiver: Option<Receiver<SendEvent>>,
+    total_count: usize,
+    sent_count: usize,
+    failed_count: usize,
+    skipped_count: usize,
+    resume_campaign: bool,
     contacts_preview: String,
 }
 
@@ -29,14 +131,33 @@ impl Default for WhatsAppSenderApp {
     fn default() -> Self {
         Self {
             excel_path: String::new(),
+            column_mapping: ColumnMapping::default(),
+            mapping_headers: Vec::new(),
+            sheet_names: Vec::new(),
+            selected_sheet: String::new(),
+            header_row: 0,
             message_template: String::from("Olá {nome}, tudo bem? Gostaria de conversar sobre..."),
+            attachment_path: String::new(),
             delay_seconds: 10,
+            retry_policy: RetryPolicy::default(),
+            transport_kind: TransportKind::WebDriver,
+            transport_config: TransportConfig::default(),
             status_text: String::from("Pronto para iniciar."),
             progress: 0.0,
             excel_handler: None,
             message_handler: MessageHandler::new(),
             is_sending: false,
+            is_paused: false,
             sending_thread: None,
+            run_control: None,
+            qr_code_texture: None,
+            availability_report: None,
+            event_receiver: None,
+            total_count: 0,
+            sent_count: 0,
+            failed_count: 0,
+            skipped_count: 0,
+            resume_campaign: true,
             contacts_preview: String::new(),
         }
     }
@@ -44,6 +165,8 @@ impl Default for WhatsAppSenderApp {
 
 impl App for WhatsAppSenderApp {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        self.process_send_events(ctx);
+
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("Enviador de Mensagens WhatsApp");
@@ -67,11 +190,40 @@ impl App for WhatsAppSenderApp {
             ctx.request_repaint();
         }
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, TRANSPORT_KIND_KEY, &self.transport_kind);
+        eframe::set_value(storage, TRANSPORT_CONFIG_KEY, &self.transport_config);
+        eframe::set_value(storage, COLUMN_MAPPING_KEY, &self.column_mapping);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Cancelar o envio e aguardar o fechamento do navegador
+        if self.is_sending {
+            self.stop_sending();
+            self.finish_sending();
+        }
+    }
 }
 
 impl WhatsAppSenderApp {
-    fn new(_cc: &CreationContext<'_>) -> Self {
-        Self::default()
+    fn new(cc: &CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        
+        // Restaurar as configurações de envio da última execução
+        if let Some(storage) = cc.storage {
+            if let Some(kind) = eframe::get_value(storage, TRANSPORT_KIND_KEY) {
+                app.transport_kind = kind;
+            }
+            if let Some(config) = eframe::get_value(storage, TRANSPORT_CONFIG_KEY) {
+                app.transport_config = config;
+            }
+            if let Some(mapping) = eframe::get_value(storage, COLUMN_MAPPING_KEY) {
+                app.column_mapping = mapping;
+            }
+        }
+        
+        app
     }
 
     fn render_excel_section(&mut self, ui: &mut Ui) {
@@ -79,46 +231,139 @@ impl WhatsAppSenderApp {
             ui.heading("Arquivo Excel");
             ui.horizontal(|ui| {
                 ui.label("Caminho: ");
-                ui.text_edit_singleline(&mut self.excel_path);
+                // Outro arquivo: as abas e a escolha manual do arquivo anterior não valem mais
+                if ui.text_edit_singleline(&mut self.excel_path).changed() {
+                    self.selected_sheet.clear();
+                    self.sheet_names.clear();
+                    self.mapping_headers.clear();
+                    self.excel_handler = None;
+                    self.contacts_preview.clear();
+                }
                 if ui.button("Selecionar").clicked() {
                     // Em uma implementação completa, abriríamos um diálogo de arquivo aqui
                     // Como simplificação, apenas simulamos a seleção
-                    self.status_text = "Selecione um arquivo Excel com colunas 'Nome' e 'Numero'.".to_string();
+                    self.status_text = "Selecione uma planilha (xlsx, xls, ods ou csv) com colunas 'Nome' e 'Numero' (opcional: 'Anexo', ex.: boletos/{cpf}.pdf). A aba e a linha do cabeçalho são detectadas automaticamente.".to_string();
                 }
             });
 
             if ui.button("Visualizar Contatos").clicked() {
                 if !self.excel_path.is_empty() {
-                    match ExcelHandler::new(&self.excel_path) {
-                        Ok(handler) => {
-                            self.excel_handler = Some(handler);
-                            self.contacts_preview = self.excel_handler.as_ref().unwrap().get_preview(5);
-                            self.status_text = format!(
-                                "Arquivo carregado com sucesso. {} contatos encontrados.",
-                                self.excel_handler.as_ref().unwrap().get_contact_count()
-                            );
-                        }
-                        Err(e) => {
-                            self.status_text = format!("Erro ao carregar arquivo: {}", e);
-                        }
-                    }
+                    self.load_contacts();
                 } else {
                     self.status_text = "Selecione um arquivo Excel primeiro.".to_string();
                 }
             }
+            
+            ui.horizontal(|ui| {
+                ui.label("Aba: ");
+                egui::ComboBox::from_id_source("sheet_name")
+                    .selected_text(if self.selected_sheet.is_empty() { "(automático)" } else { self.selected_sheet.as_str() })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.selected_sheet, String::new(), "(automático)");
+                        for name in &self.sheet_names {
+                            ui.selectable_value(&mut self.selected_sheet, name.clone(), name.as_str());
+                        }
+                    });
+                
+                ui.label("Linha do cabeçalho: ");
+                ui.add(egui::DragValue::new(&mut self.header_row).clamp_range(0..=1000));
+                if self.header_row == 0 {
+                    ui.label("(automática)");
+                }
+            });
+            
+            // Detecção automática falhou: o usuário escolhe as colunas de nome e telefone
+            if !self.mapping_headers.is_empty() {
+                let mut apply = false;
+                ui.group(|ui| {
+                    ui.label("Não foi possível identificar as colunas. Escolha quais colunas usar:");
+                    for (label, id, column) in [
+                        ("Nome: ", "nome_column", &mut self.column_mapping.nome_column),
+                        ("Telefone: ", "numero_column", &mut self.column_mapping.numero_column),
+                    ] {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            egui::ComboBox::from_id_source(id)
+                                .selected_text(if column.is_empty() { "(automático)" } else { column.as_str() })
+                                .show_ui(ui, |ui| {
+                                    for header in &self.mapping_headers {
+                                        ui.selectable_value(column, header.clone(), header.as_str());
+                                    }
+                                });
+                        });
+                    }
+                    apply = ui.button("Aplicar e carregar").clicked();
+                });
+                if apply {
+                    self.load_contacts();
+                }
+            }
+            
+            ui.collapsing("Nomes de colunas reconhecidos", |ui| {
+                ui.label("Separados por vírgula; maiúsculas, acentos e pontuação são ignorados.");
+                let mapping = &mut self.column_mapping;
+                for (label, aliases) in [
+                    ("Nome: ", &mut mapping.nome_aliases),
+                    ("Telefone: ", &mut mapping.numero_aliases),
+                    ("Email: ", &mut mapping.email_aliases),
+                    ("Empresa: ", &mut mapping.empresa_aliases),
+                    ("Anexo: ", &mut mapping.anexo_aliases),
+                ] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.text_edit_singleline(aliases);
+                    });
+                }
+                
+                if !mapping.nome_column.is_empty() || !mapping.numero_column.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "Escolha manual: nome = '{}', telefone = '{}'",
+                            mapping.nome_column, mapping.numero_column
+                        ));
+                        if ui.button("Limpar").clicked() {
+                            mapping.nome_column.clear();
+                            mapping.numero_column.clear();
+                        }
+                    });
+                }
+            });
 
             if !self.contacts_preview.is_empty() {
                 ui.collapsing("Prévia dos Contatos", |ui| {
                     ui.monospace(&self.contacts_preview);
                 });
             }
+            
+            if let Some(handler) = &self.excel_handler {
+                let coerced = handler.get_coerced_cells();
+                if !coerced.is_empty() {
+                    ui.collapsing(format!("Células convertidas ({})", coerced.len()), |ui| {
+                        for cell in coerced {
+                            ui.label(format!(
+                                "Linha {}, coluna '{}': {} lido como \"{}\"",
+                                cell.row, cell.column, cell.original_type, cell.value
+                            ));
+                        }
+                    });
+                }
+            }
         });
     }
 
     fn render_message_section(&mut self, ui: &mut Ui) {
         ui.group(|ui| {
             ui.heading("Mensagem");
-            ui.label("Digite sua mensagem abaixo. Use {nome} para inserir o nome do contato:");
+            ui.label("Digite sua mensagem abaixo. Use {nome} para inserir o nome do contato ou {coluna} para qualquer coluna da planilha:");
+            
+            if let Some(handler) = &self.excel_handler {
+                let variables: Vec<String> = handler
+                    .get_headers()
+                    .iter()
+                    .map(|header| format!("{{{}}}", header.to_lowercase()))
+                    .collect();
+                ui.label(format!("Variáveis disponíveis: {}", variables.join(", ")));
+            }
             
             ui.horizontal(|ui| {
                 if ui.button("Usar Template").clicked() {
@@ -128,6 +373,14 @@ impl WhatsAppSenderApp {
             });
             
             ui.text_edit_multiline(&mut self.message_template);
+            
+            ui.horizontal(|ui| {
+                ui.label("Anexo (opcional): ");
+                ui.add(
+                    TextEdit::singleline(&mut self.attachment_path)
+                        .hint_text("imagem, vídeo, áudio ou documento; a mensagem vira a legenda"),
+                );
+            });
         });
     }
 
@@ -138,6 +391,164 @@ impl WhatsAppSenderApp {
                 ui.label("Tempo entre mensagens (segundos): ");
                 ui.add(egui::Slider::new(&mut self.delay_seconds, 5..=60));
             });
+            
+            ui.horizontal(|ui| {
+                ui.label("Tentativas por contato: ");
+                ui.add(egui::Slider::new(&mut self.retry_policy.max_attempts, 1..=5));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Espera inicial entre tentativas (segundos): ");
+                ui.add(egui::Slider::new(&mut self.retry_policy.initial_backoff_seconds, 1..=60));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Reconexões automáticas se a sessão cair: ");
+                ui.add(egui::Slider::new(&mut self.retry_policy.max_session_recoveries, 0..=10));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Modo de envio: ");
+                egui::ComboBox::from_id_source("transport_kind")
+                    .selected_text(self.transport_kind.label())
+                    .show_ui(ui, |ui| {
+                        for kind in TransportKind::all() {
+                            ui.selectable_value(&mut self.transport_kind, kind, kind.label());
+                        }
+                    });
+            });
+            
+            if self.transport_kind == TransportKind::WebDriver {
+                ui.horizontal(|ui| {
+                    ui.label("Idioma do WhatsApp Web: ");
+                    egui::ComboBox::from_id_source("selector_language")
+                        .selected_text(self.transport_config.selector_language.as_str())
+                        .show_ui(ui, |ui| {
+                            for language in SelectorProfile::available_languages() {
+                                ui.selectable_value(
+                                    &mut self.transport_config.selector_language,
+                                    language.to_string(),
+                                    language,
+                                );
+                            }
+                        });
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("Conta remetente: ");
+                    ui.add(
+                        TextEdit::singleline(&mut self.transport_config.sender_account)
+                            .hint_text("vazio = sem sessão salva"),
+                    );
+                    ui.label("Pasta de perfis: ");
+                    ui.text_edit_singleline(&mut self.transport_config.browser_profiles_dir);
+                });
+                
+                if let Some(profile_dir) = self.transport_config.get_browser_profile_dir() {
+                    ui.horizontal(|ui| {
+                        let button = ui.add_enabled(!self.is_sending, Button::new("Sair / Esquecer sessão salva"));
+                        if button.clicked() {
+                            self.status_text = match WhatsAppAutomation::forget_session(&profile_dir) {
+                                Ok(()) => format!(
+                                    "Sessão da conta '{}' apagada. Escaneie o código QR no próximo envio.",
+                                    self.transport_config.sender_account.trim()
+                                ),
+                                Err(e) => format!("Erro ao apagar a sessão: {:#}", e),
+                            };
+                        }
+                    });
+                }
+                
+                ui.collapsing("Navegador e WebDriver", |ui| {
+                    let browser = &mut self.transport_config.browser;
+                    ui.horizontal(|ui| {
+                        ui.label("Navegador: ");
+                        egui::ComboBox::from_id_source("browser_kind")
+                            .selected_text(browser.kind.label())
+                            .show_ui(ui, |ui| {
+                                for kind in BrowserKind::all() {
+                                    ui.selectable_value(&mut browser.kind, kind, kind.label());
+                                }
+                            });
+                    });
+                    ui.checkbox(&mut browser.manage_driver, "Iniciar o WebDriver automaticamente");
+                    if browser.manage_driver {
+                        ui.horizontal(|ui| {
+                            ui.label("Executável do driver: ");
+                            ui.add(TextEdit::singleline(&mut browser.driver_path).hint_text("vazio = procurar no PATH"));
+                        });
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label("URL do WebDriver: ");
+                            ui.text_edit_singleline(&mut browser.webdriver_url);
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Endereço do WhatsApp Web: ");
+                        ui.text_edit_singleline(&mut browser.whatsapp_web_url);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Executável do navegador: ");
+                        ui.add(TextEdit::singleline(&mut browser.binary_path).hint_text("vazio = padrão do driver"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut browser.headless, "Sem janela (headless)");
+                        ui.label("Janela: ");
+                        ui.add(egui::DragValue::new(&mut browser.window_width).clamp_range(640..=3840).suffix(" px"));
+                        ui.label("x");
+                        ui.add(egui::DragValue::new(&mut browser.window_height).clamp_range(480..=2160).suffix(" px"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Idioma do navegador: ");
+                        ui.add(TextEdit::singleline(&mut browser.language).desired_width(60.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Argumentos extras: ");
+                        ui.add(TextEdit::singleline(&mut browser.extra_args).hint_text("--proxy-server=... --disable-gpu"));
+                    });
+                });
+            }
+            
+            if self.transport_kind == TransportKind::Http {
+                ui.horizontal(|ui| {
+                    ui.label("URL do servidor: ");
+                    ui.text_edit_singleline(&mut self.transport_config.http_url);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Token de acesso: ");
+                    ui.add(TextEdit::singleline(&mut self.transport_config.http_token).password(true));
+                });
+            }
+            
+            if self.transport_kind == TransportKind::CloudApi {
+                let cloud = &mut self.transport_config.cloud;
+                ui.horizontal(|ui| {
+                    ui.label("URL base da API: ");
+                    ui.text_edit_singleline(&mut cloud.base_url);
+                    ui.label("Versão: ");
+                    ui.add(TextEdit::singleline(&mut cloud.api_version).desired_width(60.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("ID do número de telefone: ");
+                    ui.text_edit_singleline(&mut cloud.phone_number_id);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Token de acesso: ");
+                    ui.add(TextEdit::singleline(&mut cloud.access_token).password(true));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Modelo aprovado (vazio = texto livre): ");
+                    ui.text_edit_singleline(&mut cloud.template_name);
+                    ui.label("Idioma: ");
+                    ui.add(TextEdit::singleline(&mut cloud.template_language).desired_width(60.0));
+                });
+                if !cloud.template_name.trim().is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label("Colunas dos parâmetros {{1}}, {{2}}...: ");
+                        ui.add(TextEdit::singleline(&mut cloud.template_parameters).hint_text("nome, valor, vencimento"));
+                    });
+                }
+            }
         });
     }
 
@@ -146,6 +557,29 @@ impl WhatsAppSenderApp {
             ui.heading("Status");
             ui.label(&self.status_text);
             
+            if let Some(report) = &self.availability_report {
+                egui::CollapsingHeader::new("Verificação do WhatsApp Web").default_open(true).show(ui, |ui| {
+                    for check in &report.checks {
+                        let color = match check.status {
+                            CheckStatus::Passed => egui::Color32::from_rgb(0, 140, 0),
+                            CheckStatus::Warning => egui::Color32::from_rgb(200, 140, 0),
+                            CheckStatus::Failed => egui::Color32::RED,
+                            CheckStatus::Skipped => egui::Color32::GRAY,
+                        };
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, check.status.symbol());
+                            ui.strong(&check.name);
+                            ui.label(&check.detail);
+                        });
+                    }
+                });
+            }
+            
+            if let Some(texture) = &self.qr_code_texture {
+                ui.label("Escaneie o código QR com o WhatsApp do celular (Aparelhos conectados > Conectar um aparelho):");
+                ui.add(egui::Image::new(texture).max_size(egui::vec2(280.0, 280.0)));
+            }
+            
             // Barra de progresso
             if self.progress > 0.0 {
                 ui.label("Progresso:");
@@ -153,6 +587,24 @@ impl WhatsAppSenderApp {
                 ui.add(progress_bar);
             }
             
+            if self.total_count > 0 {
+                ui.label(format!(
+                    "Enviadas: {} | Falhas: {} | Já enviadas antes: {} | Restantes: {}",
+                    self.sent_count,
+                    self.failed_count,
+                    self.skipped_count,
+                    self.total_count.saturating_sub(self.get_processed_count())
+                ));
+            }
+            
+            // Oferecer a retomada quando existe um diário de uma campanha anterior
+            if !self.is_sending && !self.excel_path.is_empty() && CampaignJournal::exists(&self.excel_path) {
+                ui.checkbox(
+                    &mut self.resume_campaign,
+                    "Retomar campanha anterior (ignorar contatos que já receberam a mensagem)",
+                );
+            }
+            
             ui.horizontal(|ui| {
                 let button_text = if self.is_sending { "Parar Envio" } else { "Iniciar Envio" };
                 let button = ui.add_enabled(!self.excel_path.is_empty() || self.is_sending, Button::new(button_text));
@@ -164,113 +616,737 @@ impl WhatsAppSenderApp {
                         self.start_sending();
                     }
                 }
+                
+                if self.is_sending {
+                    let pause_text = if self.is_paused { "Retomar" } else { "Pausar" };
+                    if ui.button(pause_text).clicked() {
+                        self.toggle_pause();
+                    }
+                }
             });
         });
     }
 
-    fn start_sending(&mut self) {
-        if self.excel_handler.is_none() {
-            match ExcelHandler::new(&self.excel_path) {
-                Ok(handler) => {
-                    self.excel_handler = Some(handler);
+    // Carrega a planilha com o mapeamento de colunas atual. Se as colunas obrigatórias
+    // não forem encontradas, prepara a escolha manual com os cabeçalhos disponíveis.
+    fn load_contacts(&mut self) -> bool {
+        let selection = SheetSelection {
+            sheet_name: Some(self.selected_sheet.clone()).filter(|name| !name.is_empty()),
+            header_row: Some(self.header_row as usize).filter(|row| *row > 0),
+        };
+        
+        match ExcelHandler::new(&self.excel_path, &self.column_mapping, &selection) {
+            Ok(handler) => {
+                self.mapping_headers.clear();
+                self.sheet_names = handler.get_sheet_names().to_vec();
+                self.contacts_preview = handler.get_preview(5);
+                self.status_text = format!(
+                    "Arquivo {} carregado com sucesso (aba '{}', cabeçalho na linha {}). {} contatos encontrados.",
+                    handler.get_format().as_str(),
+                    handler.get_sheet_name(),
+                    handler.get_header_row(),
+                    handler.get_contact_count()
+                );
+                let coerced = handler.get_coerced_cells().len();
+                if coerced > 0 {
+                    self.status_text.push_str(&format!(
+                        "\n{} célula(s) que não eram texto foram convertidas; confira em \"Células convertidas\".",
+                        coerced
+                    ));
+                }
+                self.excel_handler = Some(handler);
+                true
+            }
+            Err(e) => {
+                // Não enviar para a planilha carregada antes, que não corresponde mais à escolha atual
+                self.excel_handler = None;
+                self.contacts_preview.clear();
+                if let Some(missing) = e.downcast_ref::<MissingColumnsError>() {
+                    self.mapping_headers = missing.headers.clone();
+                    self.sheet_names = missing.sheet_names.clone();
                 }
+                self.status_text = format!("Erro ao carregar arquivo: {}", e);
+                false
+            }
+        }
+    }
+
+    fn start_sending(&mut self) {
+        // Recarregar sempre, para respeitar a aba, o cabeçalho e o mapeamento escolhidos por último
+        if !self.load_contacts() {
+            return;
+        }
+
+        // Validar o anexo antes de abrir o navegador
+        let attachment_path = match self.attachment_path.trim() {
+            "" => None,
+            path => match Attachment::from_path(Path::new(path)) {
+                Ok(attachment) => Some(attachment.path),
                 Err(e) => {
-                    self.status_text = format!("Erro ao carregar arquivo: {}", e);
+                    self.status_text = format!("{:#}", e);
                     return;
                 }
+            },
+        };
+        
+        // Anexos por contato (coluna 'Anexo') precisam existir antes de começar
+        let problems = self.excel_handler.as_ref().unwrap().validate_attachments();
+        if !problems.is_empty() {
+            const MAX_LISTED: usize = 10;
+            let mut text = format!("{} contato(s) com anexo inválido ou ausente:\n", problems.len());
+            for problem in problems.iter().take(MAX_LISTED) {
+                text.push_str(&format!("- {}\n", problem));
             }
+            if problems.len() > MAX_LISTED {
+                text.push_str(&format!("... e mais {}", problems.len() - MAX_LISTED));
+            }
+            self.status_text = text;
+            return;
         }
-
+        
         let excel_handler = self.excel_handler.as_ref().unwrap().clone();
-        let _message_template = self.message_template.clone();
-        let delay_seconds = self.delay_seconds;
+        let settings = CampaignSettings {
+            message_template: self.message_template.clone(),
+            attachment_path,
+            delay_seconds: self.delay_seconds,
+            retry_policy: self.retry_policy,
+        };
+        let transport = match create_transport(self.transport_kind, &self.transport_config) {
+            Ok(transport) => transport,
+            Err(e) => {
+                self.status_text = format!("Erro ao preparar o envio: {:#}", e);
+                return;
+            }
+        };
         
-        // Criar canais para comunicação entre threads
-        let progress = Arc::new(Mutex::new(0.0));
-        let status = Arc::new(Mutex::new(String::from("Iniciando envio...")));
-        let is_running = Arc::new(Mutex::new(true));
+        // O WhatsApp Web é verificado em um navegador separado antes da campanha
+        let preflight = if self.transport_kind == TransportKind::WebDriver {
+            match create_whatsapp_automation(&self.transport_config) {
+                Ok(automation) => Some(automation),
+                Err(e) => {
+                    self.status_text = format!("Erro ao preparar o envio: {:#}", e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        self.availability_report = None;
+        
+        // Sem retomada, a campanha começa com um diário limpo
+        if !self.resume_campaign {
+            if let Err(e) = CampaignJournal::reset(excel_handler.get_file_path()) {
+                self.status_text = format!("Erro ao reiniciar o diário de envio: {}", e);
+                return;
+            }
+        }
+        
+        let mut journal = match CampaignJournal::open(excel_handler.get_file_path()) {
+            Ok(journal) => journal,
+            Err(e) => {
+                self.status_text = format!("Erro ao abrir o diário de envio: {}", e);
+                return;
+            }
+        };
+        let interrupted = journal.get_interrupted_count();
         
-        let progress_clone = Arc::clone(&progress);
-        let status_clone = Arc::clone(&status);
-        let is_running_clone = Arc::clone(&is_running);
+        // Canal de eventos da thread de envio para a interface
+        let (event_tx, event_rx) = mpsc::channel();
+        let (control_tx, control_rx) = watch::channel(RunState::Running);
         
-        // Iniciar thread de envio
+        // Iniciar thread de envio com seu próprio runtime tokio
         let handle = thread::spawn(move || {
-            // Simular inicialização do navegador
-            *status_clone.lock().unwrap() = String::from("Iniciando o navegador...");
-            thread::sleep(Duration::from_secs(2));
-            
-            // Simular carregamento do WhatsApp Web
-            *status_clone.lock().unwrap() = String::from("Carregando WhatsApp Web...");
-            thread::sleep(Duration::from_secs(2));
-            
-            // Simular aguardando login
-            *status_clone.lock().unwrap() = String::from("Aguardando login no WhatsApp Web...\nPor favor, escaneie o código QR.");
-            thread::sleep(Duration::from_secs(3));
-            
-            // Simular login bem-sucedido
-            *status_clone.lock().unwrap() = String::from("Login realizado com sucesso!");
-            thread::sleep(Duration::from_secs(1));
-            
-            // Em uma implementação real, usaríamos o WhatsAppAutomation aqui
-            let contacts = excel_handler.get_contacts();
-            let total = contacts.len();
-            
-            *status_clone.lock().unwrap() = format!("Enviando mensagens para {} contatos...", total);
-            
-            // Simular envio de mensagens
-            for (i, contact) in contacts.iter().enumerate() {
-                if !*is_running_clone.lock().unwrap() {
-                    break;
+            let runtime = match tokio::runtime::Runtime::new() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = event_tx.send(SendEvent::Aborted {
+                        reason: format!("Erro ao criar o runtime assíncrono: {}", e),
+                    });
+                    return;
                 }
+            };
+            
+            if let Some(mut automation) = preflight {
+                let _ = event_tx.send(SendEvent::Stage(String::from("Verificando o WhatsApp Web...")));
                 
-                let nome = &contact.nome;
-                let _numero = &contact.numero;
-                
-                *status_clone.lock().unwrap() = format!("Enviando para {} ({}/{})", nome, i + 1, total);
-                
-                // Simular envio (em uma implementação real, usaríamos WhatsAppAutomation)
-                thread::sleep(Duration::from_secs(delay_seconds as u64));
+                // "Parar Envio" interrompe a verificação e fecha o navegador aberto por ela
+                let report = runtime.block_on(async {
+                    tokio::select! {
+                        report = automation.check_whatsapp_web_availability() => Some(report),
+                        _ = wait_for_cancel(control_rx.clone()) => {
+                            if let Err(e) = automation.close().await {
+                                log::warn!("{:#}", e);
+                            }
+                            None
+                        }
+                    }
+                });
+                // Também não abrir o navegador da campanha se o cancelamento chegou logo após a verificação
+                let Some(report) = report.filter(|_| *control_rx.borrow() != RunState::Cancelled) else {
+                    let _ = event_tx.send(SendEvent::Aborted {
+                        reason: String::from("Envio interrompido pelo usuário."),
+                    });
+                    return;
+                };
+                let ready = report.is_ready();
+                log::info!("Verificação do WhatsApp Web:\n{}", report);
+                let _ = event_tx.send(SendEvent::Preflight(report));
                 
-                // Atualizar progresso
-                *progress_clone.lock().unwrap() = (i as f32 + 1.0) / total as f32;
+                if !ready {
+                    let _ = event_tx.send(SendEvent::Aborted {
+                        reason: String::from("A verificação do WhatsApp Web encontrou problemas. Corrija-os e inicie o envio novamente."),
+                    });
+                    return;
+                }
             }
             
-            if *is_running_clone.lock().unwrap() {
-                *status_clone.lock().unwrap() = String::from("Envio concluído com sucesso!");
-            } else {
-                *status_clone.lock().unwrap() = String::from("Envio interrompido pelo usuário.");
-            }
+            let result = runtime.block_on(run_campaign(
+                transport,
+                excel_handler.get_contacts().to_vec(),
+                &settings,
+                &mut journal,
+                &event_tx,
+                control_rx.clone(),
+            ));
+            
+            let event = match result {
+                Ok(outcome) => {
+                    let aborted = if *control_rx.borrow() == RunState::Cancelled {
+                        Some(String::from("Envio interrompido pelo usuário."))
+                    } else {
+                        outcome.aborted
+                    };
+                    
+                    match aborted {
+                        // Salvar o que já foi enviado antes da interrupção
+                        Some(reason) => {
+                            let reason = if outcome.results.is_empty() {
+                                reason
+                            } else {
+                                match excel_handler.save_results(&outcome.results, None) {
+                                    Ok(path) => format!("{}\nResultados parciais salvos em {}", reason, path),
+                                    Err(e) => format!("{}\nErro ao salvar resultados: {}", reason, e),
+                                }
+                            };
+                            SendEvent::Aborted { reason }
+                        }
+                        None => {
+                            let results_path = excel_handler.save_results(&outcome.results, None)
+                                .map_err(|e| e.to_string());
+                            SendEvent::Finished { results_path }
+                        }
+                    }
+                }
+                Err(e) => SendEvent::Aborted {
+                    reason: format!("Erro durante o envio: {}", e),
+                },
+            };
+            
+            let _ = event_tx.send(event);
         });
         
         self.sending_thread = Some(handle);
+        self.event_receiver = Some(event_rx);
+        self.run_control = Some(control_tx);
         self.is_sending = true;
+        self.is_paused = false;
         
-        // Em uma implementação real, usaríamos um canal para comunicação entre threads
-        // Como simplificação, apenas atualizamos diretamente
         self.progress = 0.0;
-        self.status_text = String::from("Iniciando envio...");
-        
-        // Iniciar thread para atualizar UI
-        let _progress_app = Arc::clone(&progress);
-        let _status_app = Arc::clone(&status);
+        self.total_count = 0;
+        self.sent_count = 0;
+        self.failed_count = 0;
+        self.skipped_count = 0;
+        self.status_text = if interrupted > 0 {
+            format!(
+                "Iniciando envio...\n{} contato(s) tiveram o envio interrompido na última execução e serão tentados novamente.",
+                interrupted
+            )
+        } else {
+            String::from("Iniciando envio...")
+        };
+    }
+
+    // Consome os eventos da thread de envio e atualiza a interface
+    fn process_send_events(&mut self, ctx: &Context) {
+        let events: Vec<SendEvent> = match &self.event_receiver {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return,
+        };
         
-        thread::spawn(move || {
-            while *is_running.lock().unwrap() {
-                thread::sleep(Duration::from_millis(100));
-                // Em uma implementação real, atualizaríamos a UI aqui
+        for event in events {
+            match event {
+                SendEvent::Stage(text) => {
+                    self.status_text = text;
+                }
+                SendEvent::LoginRequired => {
+                    self.status_text = String::from(
+                        "Aguardando login no WhatsApp Web...\nSe a sessão salva não for restaurada, escaneie o código QR.",
+                    );
+                }
+                SendEvent::LoggedIn => {
+                    self.qr_code_texture = None;
+                    self.status_text = String::from("Sessão do WhatsApp conectada.");
+                }
+                SendEvent::Preflight(report) => {
+                    self.availability_report = Some(report);
+                }
+                SendEvent::QrCode(png) => {
+                    match decode_qr_code(&png) {
+                        Ok(image) => {
+                            self.qr_code_texture = Some(ctx.load_texture("qr_code", image, Default::default()));
+                        }
+                        Err(e) => {
+                            self.status_text = format!("Erro ao exibir o código QR: {}", e);
+                        }
+                    }
+                }
+                SendEvent::Paused => {
+                    self.status_text = String::from("Envio pausado. O navegador continua aberto.");
+                }
+                SendEvent::Resumed => {
+                    self.status_text = String::from("Envio retomado.");
+                }
+                SendEvent::Started { total, skipped } => {
+                    self.total_count = total;
+                    self.skipped_count = skipped;
+                    self.update_progress();
+                    self.status_text = if skipped > 0 {
+                        format!(
+                            "Retomando campanha: {} de {} contatos já receberam a mensagem e serão ignorados.",
+                            skipped, total
+                        )
+                    } else {
+                        format!("Login realizado com sucesso! Enviando mensagens para {} contatos...", total)
+                    };
+                }
+                SendEvent::ContactSent { index, nome, delivery_status } => {
+                    self.sent_count += 1;
+                    self.status_text = match delivery_status {
+                        Some(status) => format!(
+                            "Mensagem enviada para {} ({}/{}) - {}",
+                            nome,
+                            index + 1,
+                            self.total_count,
+                            status.as_str()
+                        ),
+                        None => format!("Mensagem enviada para {} ({}/{})", nome, index + 1, self.total_count),
+                    };
+                    self.update_progress();
+                }
+                SendEvent::ContactFailed { index, nome, error } => {
+                    self.failed_count += 1;
+                    self.status_text = format!("Falha ao enviar para {} ({}/{}): {}", nome, index + 1, self.total_count, error);
+                    self.update_progress();
+                }
+                SendEvent::Finished { results_path } => {
+                    let summary = format!(
+                        "Envio concluído: {} enviadas, {} falhas de {} contatos.",
+                        self.sent_count, self.failed_count, self.total_count
+                    );
+                    self.status_text = match results_path {
+                        Ok(path) => format!("{}\nResultados salvos em {}", summary, path),
+                        Err(e) => format!("{}\nErro ao salvar resultados: {}", summary, e),
+                    };
+                    self.finish_sending();
+                }
+                SendEvent::Aborted { reason } => {
+                    self.status_text = reason;
+                    self.finish_sending();
+                }
             }
-        });
+        }
     }
 
-    fn stop_sending(&mut self) {
+    fn update_progress(&mut self) {
+        if self.total_count > 0 {
+            self.progress = self.get_processed_count() as f32 / self.total_count as f32;
+        }
+    }
+
+    fn get_processed_count(&self) -> usize {
+        self.sent_count + self.failed_count + self.skipped_count
+    }
+
+    fn finish_sending(&mut self) {
+        if let Some(handle) = self.sending_thread.take() {
+            let _ = handle.join();
+        }
+        self.event_receiver = None;
+        self.run_control = None;
+        self.qr_code_texture = None;
         self.is_sending = false;
+        self.is_paused = false;
+    }
+
+    fn stop_sending(&mut self) {
+        if let Some(control) = &self.run_control {
+            let _ = control.send(RunState::Cancelled);
+        }
         self.status_text = String::from("Interrompendo envio...");
-        // Em uma implementação real, sinalizaríamos para a thread parar
+    }
+
+    fn toggle_pause(&mut self) {
+        let Some(control) = &self.run_control else {
+            return;
+        };
+        
+        let next = if self.is_paused { RunState::Running } else { RunState::Paused };
+        
+        // Não sobrescrever um cancelamento já solicitado
+        control.send_if_modified(|state| {
+            if *state == RunState::Cancelled {
+                return false;
+            }
+            *state = next;
+            true
+        });
+        self.is_paused = next == RunState::Paused;
+    }
+}
+
+// Executa o envio real para cada contato e devolve o resultado de cada tentativa
+async fn run_campaign(
+    mut transport: Box<dyn MessageTransport>,
+    contacts: Vec<Contact>,
+    settings: &CampaignSettings,
+    journal: &mut CampaignJournal,
+    events: &Sender<SendEvent>,
+    control: watch::Receiver<RunState>,
+) -> Result<CampaignOutcome> {
+    let _ = events.send(SendEvent::Stage(format!("Conectando: {}...", transport.name())));
+    
+    let result = send_to_contacts(
+        transport.as_mut(),
+        &contacts,
+        settings,
+        journal,
+        events,
+        control,
+    )
+    .await;
+    
+    // Encerrar o transporte em qualquer caso, priorizando o erro do envio
+    let closed = transport.close().await;
+    let outcome = result?;
+    closed?;
+    
+    Ok(outcome)
+}
+
+async fn send_to_contacts(
+    transport: &mut dyn MessageTransport,
+    contacts: &[Contact],
+    settings: &CampaignSettings,
+    journal: &mut CampaignJournal,
+    events: &Sender<SendEvent>,
+    mut control: watch::Receiver<RunState>,
+) -> Result<CampaignOutcome> {
+    let mut message_handler = MessageHandler::new();
+    message_handler.set_template(&settings.message_template);
+    
+    if !connect_and_login(transport, events, &control).await? {
+        return Ok(CampaignOutcome { results: Vec::new(), aborted: None });
+    }
+    
+    let total = contacts.len();
+    let mut results = Vec::with_capacity(total);
+    
+    // Contatos já marcados como enviados no diário são ignorados. A lista é tirada antes do envio:
+    // outra linha com o mesmo número (ex.: um segundo boleto) ainda recebe a sua mensagem nesta execução.
+    let already_sent: HashSet<String> = contacts
+        .iter()
+        .map(|contact| WhatsAppAutomation::format_phone_number(&contact.numero))
+        .filter(|numero| journal.is_sent(numero))
+        .collect();
+    let skipped = contacts
+        .iter()
+        .filter(|contact| already_sent.contains(&WhatsAppAutomation::format_phone_number(&contact.numero)))
+        .count();
+    
+    let _ = events.send(SendEvent::Started { total, skipped });
+    
+    // Reconexões feitas nesta campanha, limitadas pela política
+    let mut recoveries = 0;
+    
+    for (i, contact) in contacts.iter().enumerate() {
+        let numero = WhatsAppAutomation::format_phone_number(&contact.numero);
+        
+        if already_sent.contains(&numero) {
+            results.push(ContactResult::new(contact, SendStatus::Skipped, None, String::new()));
+            continue;
+        }
+        
+        if !wait_while_paused(&mut control, events).await {
+            break;
+        }
+        
+        let _ = events.send(SendEvent::Stage(format!("Enviando para {} ({}/{})", contact.nome, i + 1, total)));
+        
+        let contact_data = contact.to_template_data();
+        
+        let mensagem = message_handler.personalize_message(&contact_data);
+        
+        // Registrar a tentativa antes de enviar, para detectar quedas durante o envio
+        journal.record(&numero, JournalStatus::Attempting, None)?;
+        
+        // O anexo do contato tem prioridade sobre o anexo comum da campanha
+        let attachment = contact.anexo.as_deref().or(settings.attachment_path.as_deref());
+        
+        let (outcome, attempts) = if WhatsAppAutomation::is_valid_phone_number(&numero) {
+            let message = OutgoingMessage {
+                contact,
+                numero: &numero,
+                mensagem: &mensagem,
+                contact_data: &contact_data,
+                attachment,
+            };
+            send_with_retry(transport, &message, settings, events, &mut control, &mut recoveries).await
+        } else {
+            let error = SendError::new(SendErrorKind::InvalidNumber, format!("Número inválido: {}", contact.numero));
+            (Err(error.into()), 0)
+        };
+        
+        match outcome {
+            Ok(receipt) => {
+                journal.record(&numero, JournalStatus::Sent, None)?;
+                let _ = events.send(SendEvent::ContactSent {
+                    index: i,
+                    nome: contact.nome.clone(),
+                    delivery_status: receipt.delivery_status,
+                });
+                results.push(
+                    ContactResult::new(contact, SendStatus::Sent, None, mensagem)
+                        .with_attempts(attempts)
+                        .with_attachment(attachment)
+                        .with_receipt(receipt),
+                );
+            }
+            Err(e) => {
+                let kind = SendError::classify(&e);
+                let error = format!("{:#}", e);
+                journal.record(&numero, JournalStatus::Failed, Some(&error))?;
+                let _ = events.send(SendEvent::ContactFailed {
+                    index: i,
+                    nome: contact.nome.clone(),
+                    error: format!("{} ({})", error, kind.as_str()),
+                });
+                let status = if kind == SendErrorKind::NotOnWhatsApp {
+                    SendStatus::NotOnWhatsApp
+                } else {
+                    SendStatus::Failed
+                };
+                results.push(
+                    ContactResult::new(contact, status, Some(error), mensagem)
+                        .with_attempts(attempts)
+                        .with_error_kind(kind),
+                );
+                
+                // Credenciais recusadas: os contatos restantes falhariam todos pelo mesmo motivo
+                if kind == SendErrorKind::Unauthorized {
+                    let reason = format!(
+                        "{} recusou as credenciais. Verifique o token nas configurações e inicie o envio novamente.",
+                        transport.name()
+                    );
+                    return Ok(CampaignOutcome { results, aborted: Some(reason) });
+                }
+                
+                // Sem sessão, os contatos restantes falhariam todos; o diário permite retomar depois
+                if kind == SendErrorKind::SessionLost && *control.borrow() != RunState::Cancelled {
+                    let reason = format!(
+                        "A sessão do WhatsApp foi perdida e não pôde ser recuperada após {} tentativa(s). \
+                         Inicie o envio novamente para retomar a partir de {}.",
+                        recoveries,
+                        contact.nome
+                    );
+                    return Ok(CampaignOutcome { results, aborted: Some(reason) });
+                }
+            }
+        }
+        
+        // Aguardar entre mensagens (exceto após a última)
+        if i + 1 < total && !interruptible_delay(Duration::from_secs(settings.delay_seconds as u64), &mut control).await {
+            break;
+        }
+    }
+    
+    Ok(CampaignOutcome { results, aborted: None })
+}
+
+// Envia a mensagem repetindo as falhas transitórias conforme a política de novas tentativas.
+// Devolve o resultado da última tentativa e quantas tentativas foram feitas.
+async fn send_with_retry(
+    transport: &mut dyn MessageTransport,
+    message: &OutgoingMessage<'_>,
+    settings: &CampaignSettings,
+    events: &Sender<SendEvent>,
+    control: &mut watch::Receiver<RunState>,
+    recoveries: &mut u32,
+) -> (Result<SendReceipt>, u32) {
+    let OutgoingMessage { contact, numero, mensagem, contact_data, attachment } = *message;
+    let mut attempt = 1;
+    
+    loop {
+        let sent = match attachment {
+            Some(path) => {
+                let caption = Some(mensagem).filter(|mensagem| !mensagem.trim().is_empty());
+                transport.send_media(numero, path, caption, contact_data).await
+            }
+            None => transport.send_text(numero, mensagem, contact_data).await,
+        };
+        let error = match sent {
+            Ok(receipt) => return (Ok(receipt), attempt),
+            Err(e) => e,
+        };
+        
+        let kind = SendError::classify(&error);
+        
+        // Sessão perdida: recriar o navegador e repetir o mesmo contato
+        if settings.retry_policy.should_recover_session(kind, *recoveries) {
+            *recoveries += 1;
+            let _ = events.send(SendEvent::Stage(format!(
+                "A sessão do navegador foi perdida. Reconectando ({}/{})...",
+                recoveries,
+                settings.retry_policy.max_session_recoveries
+            )));
+            
+            match recover_session(transport, events, control).await {
+                Ok(true) => continue,
+                Ok(false) => return (Err(error), attempt),
+                Err(e) => {
+                    // Uma nova falha de sessão ainda pode ser recuperada na próxima volta
+                    if SendError::classify(&e) == SendErrorKind::SessionLost {
+                        continue;
+                    }
+                    let error = SendError::new(SendErrorKind::SessionLost, format!("Falha ao recuperar a sessão: {:#}", e));
+                    return (Err(error.into()), attempt);
+                }
+            }
+        }
+        
+        if !settings.retry_policy.should_retry(kind, attempt) {
+            return (Err(error), attempt);
+        }
+        
+        let backoff = settings.retry_policy.backoff(attempt);
+        let _ = events.send(SendEvent::Stage(format!(
+            "Falha temporária ao enviar para {} ({}). Nova tentativa em {} segundos...",
+            contact.nome,
+            kind.as_str(),
+            backoff.as_secs()
+        )));
+        
+        // Um cancelamento durante a espera encerra as tentativas com o último erro
+        if !interruptible_delay(backoff, control).await {
+            return (Err(error), attempt);
+        }
+        attempt += 1;
+    }
+}
+
+// Conecta o transporte e aguarda o login, exibindo o código QR quando necessário.
+// Retorna false se o usuário cancelou enquanto aguardava.
+async fn connect_and_login(
+    transport: &mut dyn MessageTransport,
+    events: &Sender<SendEvent>,
+    control: &watch::Receiver<RunState>,
+) -> Result<bool> {
+    transport.connect().await?;
+    
+    let _ = events.send(SendEvent::LoginRequired);
+    let on_qr_code = |png: Vec<u8>| {
+        let _ = events.send(SendEvent::QrCode(png));
+    };
+    tokio::select! {
+        result = transport.ensure_logged_in(LOGIN_TIMEOUT_SECONDS, &on_qr_code) => result?,
+        _ = wait_for_cancel(control.clone()) => return Ok(false),
+    }
+    
+    let _ = events.send(SendEvent::LoggedIn);
+    Ok(true)
+}
+
+// Descarta a sessão morta e cria outra, reaproveitando o perfil salvo do navegador
+async fn recover_session(
+    transport: &mut dyn MessageTransport,
+    events: &Sender<SendEvent>,
+    control: &watch::Receiver<RunState>,
+) -> Result<bool> {
+    // O navegador antigo pode já ter fechado; erros ao encerrá-lo são esperados
+    let _ = transport.close().await;
+    connect_and_login(transport, events, control).await
+}
+
+// Converte a captura PNG do código QR em uma imagem que o egui consegue exibir
+fn decode_qr_code(png: &[u8]) -> Result<egui::ColorImage> {
+    let image = image::load_from_memory(png)?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+// Bloqueia enquanto o envio estiver pausado. Retorna false se foi cancelado.
+async fn wait_while_paused(control: &mut watch::Receiver<RunState>, events: &Sender<SendEvent>) -> bool {
+    let mut was_paused = false;
+    
+    loop {
+        let state = *control.borrow_and_update();
+        match state {
+            RunState::Running => {
+                if was_paused {
+                    let _ = events.send(SendEvent::Resumed);
+                }
+                return true;
+            }
+            RunState::Cancelled => return false,
+            RunState::Paused => {
+                if !was_paused {
+                    let _ = events.send(SendEvent::Paused);
+                    was_paused = true;
+                }
+                // Se a interface descartou o controle, tratar como cancelamento
+                if control.changed().await.is_err() {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+// Aguarda o intervalo entre mensagens, retornando false imediatamente se o envio for cancelado
+async fn interruptible_delay(duration: Duration, control: &mut watch::Receiver<RunState>) -> bool {
+    let deadline = Instant::now() + duration;
+    
+    loop {
+        if *control.borrow_and_update() == RunState::Cancelled {
+            return false;
+        }
+        
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return true,
+            changed = control.changed() => {
+                if changed.is_err() {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+// Resolve quando o envio for cancelado pela interface
+async fn wait_for_cancel(mut control: watch::Receiver<RunState>) {
+    loop {
+        if *control.borrow_and_update() == RunState::Cancelled {
+            return;
+        }
+        if control.changed().await.is_err() {
+            return;
+        }
     }
 }
 
 fn main() -> Result<(), eframe::Error> {
+    // Logs do programa e do WebDriver; nível ajustável pela variável RUST_LOG
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0]),
@@ -283,3 +1359,165 @@ fn main() -> Result<(), eframe::Error> {
         Box::new(|cc| Box::new(WhatsAppSenderApp::new(cc))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use message_transport::MockTransport;
+    
+    fn contact(nome: &str, numero: &str) -> Contact {
+        Contact {
+            nome: nome.to_string(),
+            numero: numero.to_string(),
+            email: None,
+            empresa: None,
+            anexo: None,
+            columns: HashMap::new(),
+        }
+    }
+    
+    fn settings() -> CampaignSettings {
+        CampaignSettings {
+            message_template: String::from("Olá {nome}"),
+            attachment_path: None,
+            delay_seconds: 0,
+            // Sem espera entre as tentativas, para o teste não demorar
+            retry_policy: RetryPolicy {
+                initial_backoff_seconds: 0,
+                ..RetryPolicy::default()
+            },
+        }
+    }
+    
+    // Diário em uma pasta temporária, exclusivo de cada teste
+    fn journal(name: &str) -> (String, CampaignJournal) {
+        let workbook = std::env::temp_dir()
+            .join(format!("whatsapp_sender_{}_{}.xlsx", name, std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        CampaignJournal::reset(&workbook).unwrap();
+        let journal = CampaignJournal::open(&workbook).unwrap();
+        (workbook, journal)
+    }
+    
+    async fn run(transport: &mut MockTransport, contacts: &[Contact], journal: &mut CampaignJournal) -> CampaignOutcome {
+        run_with(transport, contacts, journal, &settings()).await
+    }
+    
+    async fn run_with(
+        transport: &mut MockTransport,
+        contacts: &[Contact],
+        journal: &mut CampaignJournal,
+        settings: &CampaignSettings,
+    ) -> CampaignOutcome {
+        let (events, _receiver) = mpsc::channel();
+        let (_control, control_rx) = watch::channel(RunState::Running);
+        send_to_contacts(transport, contacts, settings, journal, &events, control_rx)
+            .await
+            .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn sends_personalized_message_to_each_contact() {
+        let (workbook, mut journal) = journal("envio");
+        let mut transport = MockTransport::new();
+        let contacts = [contact("Ana", "11999998888"), contact("Bruno", "+55 21 98888-7777")];
+        
+        let outcome = run(&mut transport, &contacts, &mut journal).await;
+        
+        assert!(outcome.aborted.is_none());
+        assert!(outcome.results.iter().all(|result| result.status == SendStatus::Sent));
+        let sent = transport.get_sent_messages();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].numero, "+5511999998888");
+        assert_eq!(sent[0].mensagem, "Olá Ana");
+        assert_eq!(sent[1].numero, "+5521988887777");
+        assert!(journal.is_sent("+5521988887777"));
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn records_failure_and_continues_with_next_contact() {
+        let (workbook, mut journal) = journal("falha");
+        let mut transport = MockTransport::new().fail_for("+5511999998888");
+        let contacts = [contact("Ana", "11999998888"), contact("Bruno", "21988887777")];
+        
+        let outcome = run(&mut transport, &contacts, &mut journal).await;
+        
+        assert_eq!(outcome.results[0].status, SendStatus::Failed);
+        assert_eq!(outcome.results[0].attempts, 1);
+        assert_eq!(outcome.results[1].status, SendStatus::Sent);
+        assert_eq!(transport.get_sent_messages().len(), 1);
+        assert!(!journal.is_sent("+5511999998888"));
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn sends_every_row_sharing_a_number_in_a_fresh_run() {
+        let (workbook, mut journal) = journal("mesmo_numero");
+        let mut transport = MockTransport::new();
+        let contacts = [contact("Ana", "11999998888"), contact("Ana", "11999998888")];
+        
+        let outcome = run(&mut transport, &contacts, &mut journal).await;
+        
+        assert!(outcome.results.iter().all(|result| result.status == SendStatus::Sent));
+        assert_eq!(transport.get_sent_messages().len(), 2);
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn retries_transient_failure_until_it_succeeds() {
+        let (workbook, mut journal) = journal("nova_tentativa");
+        let mut transport = MockTransport::new().fail_transiently_for("+5511999998888", 2);
+        let contacts = [contact("Ana", "11999998888")];
+        
+        let outcome = run(&mut transport, &contacts, &mut journal).await;
+        
+        assert_eq!(outcome.results[0].status, SendStatus::Sent);
+        assert_eq!(outcome.results[0].attempts, 3);
+        assert_eq!(transport.get_sent_messages().len(), 1);
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn records_the_attachment_actually_sent() {
+        let (workbook, mut journal) = journal("anexo");
+        let mut transport = MockTransport::new();
+        let mut boleto = contact("Bruno", "21988887777");
+        boleto.anexo = Some(PathBuf::from("boletos/bruno.pdf"));
+        let contacts = [contact("Ana", "11999998888"), boleto];
+        let settings = CampaignSettings {
+            attachment_path: Some(PathBuf::from("catalogo.pdf")),
+            ..settings()
+        };
+        
+        let outcome = run_with(&mut transport, &contacts, &mut journal, &settings).await;
+        
+        assert_eq!(outcome.results[0].attachment, Some(PathBuf::from("catalogo.pdf")));
+        assert_eq!(outcome.results[1].attachment, Some(PathBuf::from("boletos/bruno.pdf")));
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn skips_contacts_already_sent_in_journal() {
+        let (workbook, mut journal) = journal("retomada");
+        journal.record("+5511999998888", JournalStatus::Sent, None).unwrap();
+        let mut transport = MockTransport::new();
+        let contacts = [contact("Ana", "11999998888"), contact("Bruno", "21988887777")];
+        
+        let outcome = run(&mut transport, &contacts, &mut journal).await;
+        
+        assert_eq!(outcome.results[0].status, SendStatus::Skipped);
+        assert_eq!(outcome.results[1].status, SendStatus::Sent);
+        let sent = transport.get_sent_messages();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].numero, "+5521988887777");
+        
+        CampaignJournal::reset(&workbook).unwrap();
+    }
+}
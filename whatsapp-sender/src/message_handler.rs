@@ -46,7 +46,10 @@ impl MessageHandler {
                     .or_else(|| contact_data.get("nome"))
                     .unwrap_or(&empty)
             } else {
+                // Colunas da planilha chegam com o cabeçalho em minúsculas
                 contact_data.get(placeholder)
+                    .or_else(|| contact_data.get(&placeholder_lower))
+                    .or_else(|| contact_data.get(placeholder_lower.trim()))
                     .unwrap_or(&empty)
             };
             
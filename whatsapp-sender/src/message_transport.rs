@@ -0,0 +1,395 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::cloud_api::{CloudApiConfig, CloudApiTransport};
+use crate::selector_profile::SelectorProfile;
+use crate::send_error::{SendError, SendErrorKind};
+use crate::whatsapp_automation::{BrowserConfig, WhatsAppAutomation};
+
+// Meio de entrega das mensagens usado pela campanha.
+// Cada backend (navegador, simulação, HTTP...) implementa este trait,
+// de forma que o motor de envio não depende de nenhum deles diretamente.
+#[async_trait]
+pub trait MessageTransport: Send {
+    fn name(&self) -> &str;
+    
+    async fn connect(&mut self) -> Result<()>;
+    
+    // Backends com login interativo entregam o código QR (PNG) a `on_qr_code`
+    async fn ensure_logged_in(&mut self, timeout_seconds: u64, on_qr_code: &QrCodeSink<'_>) -> Result<()>;
+    
+    // Os dados do contato permitem que o backend monte parâmetros próprios (ex.: modelos aprovados)
+    async fn send_text(
+        &mut self,
+        numero: &str,
+        mensagem: &str,
+        contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt>;
+    
+    async fn send_media(
+        &mut self,
+        numero: &str,
+        file_path: &Path,
+        caption: Option<&str>,
+        contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt>;
+    
+    async fn close(&mut self) -> Result<()>;
+}
+
+// Recebe cada nova imagem PNG do código QR exibido durante o login
+pub type QrCodeSink<'a> = dyn Fn(Vec<u8>) + Send + Sync + 'a;
+
+// Confirmação devolvida pelo backend após um envio bem-sucedido
+#[derive(Debug, Clone, Default)]
+pub struct SendReceipt {
+    // Identificador atribuído pelo serviço, quando disponível
+    pub message_id: Option<String>,
+    // Estado observado após o envio, quando o backend consegue verificá-lo
+    pub delivery_status: Option<DeliveryStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    // Ainda com o relógio: o WhatsApp não aceitou a mensagem
+    Pending,
+    Sent,
+    Delivered,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "Pendente",
+            DeliveryStatus::Sent => "Enviada",
+            DeliveryStatus::Delivered => "Entregue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransportKind {
+    WebDriver,
+    Mock,
+    Http,
+    CloudApi,
+}
+
+impl TransportKind {
+    pub fn all() -> [TransportKind; 4] {
+        [
+            TransportKind::WebDriver,
+            TransportKind::CloudApi,
+            TransportKind::Http,
+            TransportKind::Mock,
+        ]
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransportKind::WebDriver => "WhatsApp Web (navegador)",
+            TransportKind::Mock => "Simulação (sem envio real)",
+            TransportKind::Http => "API HTTP",
+            TransportKind::CloudApi => "WhatsApp Business Cloud API",
+        }
+    }
+}
+
+// Configuração necessária para criar qualquer um dos backends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransportConfig {
+    // Idioma da interface do WhatsApp Web, que define o perfil de seletores
+    pub selector_language: String,
+    // Pasta com um perfil de navegador por conta remetente
+    pub browser_profiles_dir: String,
+    // Conta remetente; vazio usa uma sessão nova (com código QR) a cada execução
+    pub sender_account: String,
+    pub browser: BrowserConfig,
+    pub http_url: String,
+    // Credenciais não são salvas junto com as configurações; o usuário as informa a cada execução
+    #[serde(skip)]
+    pub http_token: String,
+    pub cloud: CloudApiConfig,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            selector_language: String::from("pt-BR"),
+            browser_profiles_dir: String::from("perfis_navegador"),
+            sender_account: String::from("principal"),
+            browser: BrowserConfig::default(),
+            http_url: String::new(),
+            http_token: String::new(),
+            cloud: CloudApiConfig::default(),
+        }
+    }
+}
+
+impl TransportConfig {
+    // Pasta do perfil persistente do navegador para a conta remetente configurada
+    pub fn get_browser_profile_dir(&self) -> Option<PathBuf> {
+        let account = self.sender_account.trim();
+        if account.is_empty() {
+            return None;
+        }
+        
+        // Evitar que o nome da conta saia da pasta de perfis
+        let account: String = account
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        
+        Some(Path::new(self.browser_profiles_dir.trim()).join(account))
+    }
+}
+
+pub fn create_transport(kind: TransportKind, config: &TransportConfig) -> Result<Box<dyn MessageTransport>> {
+    let transport: Box<dyn MessageTransport> = match kind {
+        TransportKind::WebDriver => Box::new(create_whatsapp_automation(config)?),
+        TransportKind::Mock => Box::new(MockTransport::new()),
+        TransportKind::Http => Box::new(HttpTransport::new(&config.http_url, &config.http_token)),
+        TransportKind::CloudApi => Box::new(CloudApiTransport::new(&config.cloud)),
+    };
+    Ok(transport)
+}
+
+pub fn create_whatsapp_automation(config: &TransportConfig) -> Result<WhatsAppAutomation> {
+    let selectors = SelectorProfile::load(&config.selector_language)?;
+    Ok(WhatsAppAutomation::new()
+        .with_selector_profile(selectors)
+        .with_browser_config(config.browser.clone())
+        .with_profile_dir(config.get_browser_profile_dir()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentMessage {
+    pub numero: String,
+    pub mensagem: String,
+    pub attachment: Option<PathBuf>,
+}
+
+// Backend em memória: não envia nada, apenas registra as mensagens.
+// Permite executar a campanha completa sem navegador.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    sent: Arc<Mutex<Vec<SentMessage>>>,
+    failing_numbers: HashSet<String>,
+    // Falhas transitórias restantes por número, antes de o envio dar certo
+    transient_failures: HashMap<String, u32>,
+    connected: bool,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    // Faz o envio para o número informado falhar, para simular erros
+    #[cfg(test)]
+    pub fn fail_for(mut self, numero: &str) -> Self {
+        self.failing_numbers.insert(numero.to_string());
+        self
+    }
+    
+    // Faz os próximos `times` envios para o número falharem por tempo esgotado
+    #[cfg(test)]
+    pub fn fail_transiently_for(mut self, numero: &str, times: u32) -> Self {
+        self.transient_failures.insert(numero.to_string(), times);
+        self
+    }
+    
+    #[cfg(test)]
+    pub fn get_sent_messages(&self) -> Vec<SentMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+    
+    fn record(&mut self, numero: &str, mensagem: &str, attachment: Option<&Path>) -> Result<SendReceipt> {
+        if !self.connected {
+            return Err(anyhow::anyhow!("Transporte de simulação não conectado"));
+        }
+        
+        if self.failing_numbers.contains(numero) {
+            return Err(anyhow::anyhow!("Falha simulada para o número {}", numero));
+        }
+        
+        if let Some(remaining) = self.transient_failures.get_mut(numero).filter(|remaining| **remaining > 0) {
+            *remaining -= 1;
+            return Err(SendError::new(SendErrorKind::Timeout, format!("Tempo esgotado simulado para o número {}", numero)).into());
+        }
+        
+        let mut sent = self.sent.lock().unwrap();
+        sent.push(SentMessage {
+            numero: numero.to_string(),
+            mensagem: mensagem.to_string(),
+            attachment: attachment.map(|path| path.to_path_buf()),
+        });
+        
+        Ok(SendReceipt {
+            message_id: Some(format!("mock-{}", sent.len())),
+            delivery_status: Some(DeliveryStatus::Delivered),
+        })
+    }
+}
+
+#[async_trait]
+impl MessageTransport for MockTransport {
+    fn name(&self) -> &str {
+        "Simulação"
+    }
+    
+    async fn connect(&mut self) -> Result<()> {
+        self.connected = true;
+        Ok(())
+    }
+    
+    async fn ensure_logged_in(&mut self, _timeout_seconds: u64, _on_qr_code: &QrCodeSink<'_>) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn send_text(
+        &mut self,
+        numero: &str,
+        mensagem: &str,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        self.record(numero, mensagem, None)
+    }
+    
+    async fn send_media(
+        &mut self,
+        numero: &str,
+        file_path: &Path,
+        caption: Option<&str>,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        self.record(numero, caption.unwrap_or_default(), Some(file_path))
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+}
+
+// Backend genérico que entrega as mensagens a um gateway HTTP próprio.
+// Texto é enviado como JSON e anexos como multipart/form-data.
+pub struct HttpTransport {
+    url: String,
+    token: String,
+    client: Option<reqwest::Client>,
+}
+
+impl HttpTransport {
+    pub fn new(url: &str, token: &str) -> Self {
+        Self {
+            url: url.trim().to_string(),
+            token: token.trim().to_string(),
+            client: None,
+        }
+    }
+    
+    fn request(&self) -> Result<reqwest::RequestBuilder> {
+        let client = self.client.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cliente HTTP não inicializado"))?;
+        
+        let mut request = client.post(&self.url);
+        if !self.token.is_empty() {
+            request = request.bearer_auth(&self.token);
+        }
+        Ok(request)
+    }
+    
+    async fn check_response(response: reqwest::Response) -> Result<SendReceipt> {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("O servidor HTTP respondeu {}: {}", status, body));
+        }
+        
+        // O gateway pode devolver {"id": "..."}; qualquer outro corpo é aceito sem identificador
+        let message_id = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| value.get("id").and_then(|id| id.as_str()).map(|id| id.to_string()));
+        
+        Ok(SendReceipt {
+            message_id,
+            ..Default::default()
+        })
+    }
+}
+
+#[async_trait]
+impl MessageTransport for HttpTransport {
+    fn name(&self) -> &str {
+        "API HTTP"
+    }
+    
+    async fn connect(&mut self) -> Result<()> {
+        url::Url::parse(&self.url)
+            .with_context(|| format!("URL do servidor HTTP inválida: {}", self.url))?;
+        
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Falha ao criar o cliente HTTP")?;
+        
+        self.client = Some(client);
+        Ok(())
+    }
+    
+    async fn ensure_logged_in(&mut self, _timeout_seconds: u64, _on_qr_code: &QrCodeSink<'_>) -> Result<()> {
+        // A autenticação é feita pelo token em cada requisição
+        Ok(())
+    }
+    
+    async fn send_text(
+        &mut self,
+        numero: &str,
+        mensagem: &str,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let response = self.request()?
+            .json(&serde_json::json!({ "to": numero, "message": mensagem }))
+            .send()
+            .await
+            .context("Falha ao enviar a requisição HTTP")?;
+        
+        Self::check_response(response).await
+    }
+    
+    async fn send_media(
+        &mut self,
+        numero: &str,
+        file_path: &Path,
+        caption: Option<&str>,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let bytes = tokio::fs::read(file_path).await
+            .with_context(|| format!("Erro ao ler o anexo: {}", file_path.display()))?;
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        
+        let form = reqwest::multipart::Form::new()
+            .text("to", numero.to_string())
+            .text("caption", caption.unwrap_or_default().to_string())
+            .part("file", reqwest::multipart::Part::bytes(bytes).file_name(file_name));
+        
+        let response = self.request()?
+            .multipart(form)
+            .send()
+            .await
+            .context("Falha ao enviar o anexo via HTTP")?;
+        
+        Self::check_response(response).await
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        self.client = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// Versão do formato dos arquivos de perfil suportada por este build
+pub const PROFILE_FORMAT_VERSION: u32 = 1;
+
+// Diretório onde perfis atualizados podem ser colocados sem recompilar
+pub const PROFILE_DIR: &str = "selectors";
+
+// Perfis embutidos no executável, usados quando não há arquivo externo
+const BUILTIN_PROFILES: [(&str, &str); 2] = [
+    ("pt-BR", include_str!("../selectors/pt-BR.json")),
+    ("en", include_str!("../selectors/en.json")),
+];
+
+// Seletores XPath do WhatsApp Web para um idioma da interface.
+// Cada elemento tem uma cadeia de alternativas, tentadas em ordem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorProfile {
+    pub version: u32,
+    pub revision: String,
+    pub language: String,
+    pub logged_in_marker: Vec<String>,
+    // Canvas do código QR exibido antes do login
+    #[serde(default)]
+    pub qr_code: Vec<String>,
+    // Avisos de celular ou computador desconectado
+    #[serde(default)]
+    pub disconnected_banner: Vec<String>,
+    // Campo de digitação da conversa
+    #[serde(default)]
+    pub composer: Vec<String>,
+    pub send_button: Vec<String>,
+    pub popup: Vec<String>,
+    // Relativos ao diálogo encontrado
+    pub popup_button: Vec<String>,
+    // Trechos (em minúsculas) que identificam o aviso de número inválido
+    pub invalid_number_texts: Vec<String>,
+    // Menu de anexos e pré-visualização do arquivo
+    #[serde(default)]
+    pub attach_button: Vec<String>,
+    #[serde(default)]
+    pub media_input: Vec<String>,
+    #[serde(default)]
+    pub document_input: Vec<String>,
+    #[serde(default)]
+    pub media_caption: Vec<String>,
+    #[serde(default)]
+    pub media_send_button: Vec<String>,
+    // Trechos (em minúsculas) do aviso de anexo recusado
+    #[serde(default)]
+    pub attachment_rejected_texts: Vec<String>,
+    pub outgoing_message: Vec<String>,
+    // Relativos ao balão da mensagem
+    pub status_icon: Vec<String>,
+    pub pending_icons: Vec<String>,
+    pub sent_icons: Vec<String>,
+    pub delivered_icons: Vec<String>,
+}
+
+impl SelectorProfile {
+    pub fn available_languages() -> Vec<&'static str> {
+        BUILTIN_PROFILES.iter().map(|(language, _)| *language).collect()
+    }
+    
+    // Carrega o perfil do idioma, preferindo um arquivo externo em `selectors/<idioma>.json`
+    pub fn load(language: &str) -> Result<Self> {
+        for dir in Self::search_dirs() {
+            let path = dir.join(format!("{}.json", language));
+            if path.exists() {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Erro ao ler o perfil de seletores: {}", path.display()))?;
+                let mut profile = Self::parse(&content)
+                    .with_context(|| format!("Perfil de seletores inválido: {}", path.display()))?;
+                
+                // Arquivos anteriores aos anexos, ao código QR e ao campo de mensagem não têm essas cadeias
+                if let Ok(builtin) = Self::builtin(language) {
+                    profile.fill_missing_chains(&builtin);
+                }
+                return Ok(profile);
+            }
+        }
+        
+        Self::builtin(language)
+    }
+    
+    pub fn builtin(language: &str) -> Result<Self> {
+        let (_, content) = BUILTIN_PROFILES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(language))
+            .ok_or_else(|| anyhow::anyhow!("Não existe perfil de seletores para o idioma '{}'", language))?;
+        
+        Self::parse(content)
+    }
+    
+    fn parse(content: &str) -> Result<Self> {
+        let profile: SelectorProfile = serde_json::from_str(content)?;
+        
+        if profile.version > PROFILE_FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "O perfil usa o formato {} e esta versão do programa suporta até o formato {}",
+                profile.version,
+                PROFILE_FORMAT_VERSION
+            ));
+        }
+        
+        Ok(profile)
+    }
+    
+    // Completa as cadeias vazias com as do perfil embutido, elemento a elemento
+    fn fill_missing_chains(&mut self, builtin: &SelectorProfile) {
+        let chains = [
+            (&mut self.logged_in_marker, &builtin.logged_in_marker),
+            (&mut self.qr_code, &builtin.qr_code),
+            (&mut self.disconnected_banner, &builtin.disconnected_banner),
+            (&mut self.composer, &builtin.composer),
+            (&mut self.send_button, &builtin.send_button),
+            (&mut self.popup, &builtin.popup),
+            (&mut self.popup_button, &builtin.popup_button),
+            (&mut self.invalid_number_texts, &builtin.invalid_number_texts),
+            (&mut self.attach_button, &builtin.attach_button),
+            (&mut self.media_input, &builtin.media_input),
+            (&mut self.document_input, &builtin.document_input),
+            (&mut self.media_caption, &builtin.media_caption),
+            (&mut self.media_send_button, &builtin.media_send_button),
+            (&mut self.attachment_rejected_texts, &builtin.attachment_rejected_texts),
+            (&mut self.outgoing_message, &builtin.outgoing_message),
+            (&mut self.status_icon, &builtin.status_icon),
+            (&mut self.pending_icons, &builtin.pending_icons),
+            (&mut self.sent_icons, &builtin.sent_icons),
+            (&mut self.delivered_icons, &builtin.delivered_icons),
+        ];
+        
+        for (chain, fallback) in chains {
+            if chain.is_empty() {
+                chain.clone_from(fallback);
+            }
+        }
+    }
+    
+    // Diretório atual e diretório do executável
+    fn search_dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![PathBuf::from(PROFILE_DIR)];
+        if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.to_path_buf())) {
+            dirs.push(exe_dir.join(PROFILE_DIR));
+        }
+        dirs
+    }
+    
+    pub fn describe(&self) -> String {
+        format!("perfil {} (revisão {})", self.language, self.revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Um erro de edição nos arquivos embutidos faria WhatsAppAutomation::new entrar em pânico
+    #[test]
+    fn parses_every_builtin_profile() {
+        for (language, content) in BUILTIN_PROFILES {
+            let profile = SelectorProfile::parse(content)
+                .unwrap_or_else(|e| panic!("perfil embutido {} inválido: {:#}", language, e));
+            assert_eq!(profile.language, language);
+            assert!(!profile.send_button.is_empty(), "{}", language);
+            assert!(!profile.logged_in_marker.is_empty(), "{}", language);
+        }
+    }
+    
+    #[test]
+    fn fills_chains_missing_from_older_profile_files() {
+        let builtin = SelectorProfile::builtin("pt-BR").unwrap();
+        // Perfil externo no formato anterior aos anexos: sem as cadeias novas
+        let mut profile = builtin.clone();
+        profile.attach_button.clear();
+        profile.composer.clear();
+        profile.send_button = vec![String::from("//button[@data-teste]")];
+        
+        profile.fill_missing_chains(&builtin);
+        
+        assert_eq!(profile.attach_button, builtin.attach_button);
+        assert_eq!(profile.composer, builtin.composer);
+        assert_eq!(profile.send_button, vec![String::from("//button[@data-teste]")]);
+    }
+}
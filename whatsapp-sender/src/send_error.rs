@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+// Classificação das falhas de envio, usada para decidir se vale tentar de novo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SendErrorKind {
+    InvalidNumber,
+    // O número é válido, mas não tem conta no WhatsApp
+    NotOnWhatsApp,
+    Timeout,
+    SessionLost,
+    // Credenciais recusadas pelo serviço (token inválido ou sem permissão)
+    Unauthorized,
+    ElementNotFound,
+    // Arquivo ausente, grande demais, de tipo não suportado ou recusado pelo WhatsApp
+    Attachment,
+    Unknown,
+}
+
+impl SendErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SendErrorKind::InvalidNumber => "Número inválido",
+            SendErrorKind::NotOnWhatsApp => "Sem WhatsApp",
+            SendErrorKind::Timeout => "Tempo esgotado",
+            SendErrorKind::SessionLost => "Sessão perdida",
+            SendErrorKind::Unauthorized => "Não autorizado",
+            SendErrorKind::ElementNotFound => "Elemento não encontrado",
+            SendErrorKind::Attachment => "Falha no anexo",
+            SendErrorKind::Unknown => "Desconhecido",
+        }
+    }
+    
+    // Falhas transitórias podem ser resolvidas simplesmente tentando novamente
+    pub fn is_transient(&self) -> bool {
+        matches!(self, SendErrorKind::Timeout | SendErrorKind::ElementNotFound)
+    }
+    
+    // Reconhece a classe de uma falha pelo texto de erro do WebDriver ou do servidor
+    pub fn from_message(message: &str) -> Option<Self> {
+        let message = message.to_lowercase();
+        
+        if message.contains("invalid session id")
+            || message.contains("session deleted")
+            || message.contains("session not created")
+            || message.contains("no such window")
+            || message.contains("connection refused")
+            || message.contains("browser has closed")
+        {
+            Some(SendErrorKind::SessionLost)
+        } else if message.contains("timed out") || message.contains("timeout") || message.contains("tempo esgotado") {
+            Some(SendErrorKind::Timeout)
+        } else if message.contains("no such element") || message.contains("stale element") {
+            Some(SendErrorKind::ElementNotFound)
+        } else if message.contains("invalid phone") || message.contains("número inválido") {
+            Some(SendErrorKind::InvalidNumber)
+        } else {
+            None
+        }
+    }
+}
+
+// Erro de envio já classificado, transportado dentro de um anyhow::Error
+#[derive(Debug)]
+pub struct SendError {
+    pub kind: SendErrorKind,
+    message: String,
+}
+
+impl SendError {
+    pub fn new(kind: SendErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+    
+    // Classifica um erro externo pelo texto, usando `fallback` quando ele não é reconhecido
+    pub fn from_error(error: impl fmt::Display, fallback: SendErrorKind, context: &str) -> Self {
+        let detail = error.to_string();
+        let kind = SendErrorKind::from_message(&detail).unwrap_or(fallback);
+        Self::new(kind, format!("{}: {}", context, detail))
+    }
+    
+    // Obtém a classe de qualquer erro devolvido por um transporte
+    pub fn classify(error: &anyhow::Error) -> SendErrorKind {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<SendError>().map(|e| e.kind))
+            .or_else(|| SendErrorKind::from_message(&format!("{:#}", error)))
+            .unwrap_or(SendErrorKind::Unknown)
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SendError {}
+
+// Política de novas tentativas para falhas transitórias, com espera exponencial
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    // Total de tentativas por contato, incluindo a primeira
+    pub max_attempts: u32,
+    pub initial_backoff_seconds: u64,
+    pub max_backoff_seconds: u64,
+    // Quantas vezes a sessão perdida (navegador fechado, driver caído) é recriada por campanha
+    pub max_session_recoveries: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_seconds: 5,
+            max_backoff_seconds: 120,
+            max_session_recoveries: 3,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn should_retry(&self, kind: SendErrorKind, attempt: u32) -> bool {
+        kind.is_transient() && attempt < self.max_attempts
+    }
+    
+    pub fn should_recover_session(&self, kind: SendErrorKind, recoveries: u32) -> bool {
+        kind == SendErrorKind::SessionLost && recoveries < self.max_session_recoveries
+    }
+    
+    // Espera antes da tentativa seguinte a `attempt` (começando em 1)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        let seconds = self.initial_backoff_seconds
+            .saturating_mul(factor)
+            .min(self.max_backoff_seconds);
+        Duration::from_secs(seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn backoff_doubles_until_the_cap() {
+        let policy = RetryPolicy {
+            initial_backoff_seconds: 5,
+            max_backoff_seconds: 30,
+            ..RetryPolicy::default()
+        };
+        
+        let waits: Vec<u64> = (1..=6).map(|attempt| policy.backoff(attempt).as_secs()).collect();
+        assert_eq!(waits, [5, 10, 20, 30, 30, 30]);
+        assert_eq!(policy.backoff(200).as_secs(), 30);
+    }
+    
+    #[test]
+    fn retries_only_transient_kinds_within_the_limit() {
+        let policy = RetryPolicy::default();
+        
+        assert!(policy.should_retry(SendErrorKind::Timeout, 1));
+        assert!(policy.should_retry(SendErrorKind::ElementNotFound, 2));
+        assert!(!policy.should_retry(SendErrorKind::Timeout, policy.max_attempts));
+        
+        for kind in [
+            SendErrorKind::InvalidNumber,
+            SendErrorKind::NotOnWhatsApp,
+            SendErrorKind::SessionLost,
+            SendErrorKind::Unauthorized,
+            SendErrorKind::Attachment,
+            SendErrorKind::Unknown,
+        ] {
+            assert!(!policy.should_retry(kind, 1), "{:?}", kind);
+        }
+    }
+    
+    #[test]
+    fn classifies_webdriver_messages() {
+        for message in [
+            "WebDriver error: invalid session id",
+            "no such window: target window already closed",
+            "error sending request: Connection refused (os error 111)",
+            "The browser has closed",
+        ] {
+            assert_eq!(SendErrorKind::from_message(message), Some(SendErrorKind::SessionLost), "{}", message);
+        }
+        assert_eq!(SendErrorKind::from_message("operation timed out"), Some(SendErrorKind::Timeout));
+        assert_eq!(SendErrorKind::from_message("no such element: Unable to locate"), Some(SendErrorKind::ElementNotFound));
+        assert_eq!(SendErrorKind::from_message("algo inesperado"), None);
+    }
+    
+    #[test]
+    fn classify_prefers_the_attached_kind() {
+        let error = anyhow::Error::new(SendError::new(SendErrorKind::Attachment, "timeout ao enviar o anexo"))
+            .context("Falha no contato");
+        assert_eq!(SendError::classify(&error), SendErrorKind::Attachment);
+    }
+}
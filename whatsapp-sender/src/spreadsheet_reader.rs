@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use calamine::{Data, Ods, Reader, Sheets, Xls, Xlsb, Xlsx};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// Assinaturas dos formatos binários
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+const OLE_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+// Separadores aceitos em CSV, na ordem de preferência em caso de empate
+const CSV_DELIMITERS: [u8; 4] = [b';', b',', b'\t', b'|'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    Xlsx,
+    Xlsb,
+    Xls,
+    Ods,
+    Csv,
+}
+
+impl SourceFormat {
+    // Identifica o formato pelo conteúdo, não pela extensão
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(OLE_SIGNATURE) {
+            SourceFormat::Xls
+        } else if bytes.starts_with(ZIP_SIGNATURE) {
+            // O ODS guarda o arquivo "mimetype" sem compressão logo no início
+            if contains(&bytes[..bytes.len().min(256)], b"application/vnd.oasis.opendocument.spreadsheet") {
+                SourceFormat::Ods
+            } else if contains(bytes, b"xl/workbook.bin") {
+                SourceFormat::Xlsb
+            } else {
+                SourceFormat::Xlsx
+            }
+        } else {
+            SourceFormat::Csv
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceFormat::Xlsx => "Excel (.xlsx)",
+            SourceFormat::Xlsb => "Excel binário (.xlsb)",
+            SourceFormat::Xls => "Excel 97-2003 (.xls)",
+            SourceFormat::Ods => "OpenDocument (.ods)",
+            SourceFormat::Csv => "CSV",
+        }
+    }
+}
+
+// Linhas de uma aba, com as células no formato do calamine
+pub struct SheetData {
+    pub name: String,
+    pub rows: Vec<Vec<Data>>,
+}
+
+// Todas as abas do arquivo, na ordem em que aparecem (um CSV tem uma única aba)
+pub struct Spreadsheet {
+    pub format: SourceFormat,
+    pub sheets: Vec<SheetData>,
+}
+
+impl Spreadsheet {
+    pub fn get_sheet_names(&self) -> Vec<String> {
+        self.sheets.iter().map(|sheet| sheet.name.clone()).collect()
+    }
+}
+
+pub fn read(path: &Path) -> Result<Spreadsheet> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("Erro ao ler o arquivo: {}", path.display()))?;
+    
+    let format = SourceFormat::detect(&bytes);
+    let sheets = match format {
+        SourceFormat::Csv => vec![SheetData {
+            name: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            rows: read_csv(&bytes)?,
+        }],
+        _ => read_workbook(format, bytes)
+            .with_context(|| format!("Erro ao abrir a planilha {} ({})", path.display(), format.as_str()))?,
+    };
+    
+    if sheets.is_empty() {
+        return Err(anyhow::anyhow!("O arquivo não contém planilhas"));
+    }
+    
+    Ok(Spreadsheet { format, sheets })
+}
+
+fn read_workbook(format: SourceFormat, bytes: Vec<u8>) -> Result<Vec<SheetData>> {
+    let cursor = Cursor::new(bytes);
+    let mut workbook: Sheets<Cursor<Vec<u8>>> = match format {
+        SourceFormat::Xlsx => Sheets::Xlsx(Xlsx::new(cursor)?),
+        SourceFormat::Xlsb => Sheets::Xlsb(Xlsb::new(cursor)?),
+        SourceFormat::Xls => Sheets::Xls(Xls::new(cursor)?),
+        SourceFormat::Ods => Sheets::Ods(Ods::new(cursor)?),
+        SourceFormat::Csv => unreachable!("CSV não é lido pelo calamine"),
+    };
+    
+    let mut sheets = Vec::new();
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)
+            .with_context(|| format!("Erro ao acessar a aba '{}'", name))?;
+        // O calamine descarta as linhas vazias do topo; elas são repostas para que
+        // o índice de cada linha corresponda à numeração exibida na planilha
+        let first_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+        let mut rows = vec![Vec::new(); first_row];
+        rows.extend(range.rows().map(|row| row.to_vec()));
+        sheets.push(SheetData { name, rows });
+    }
+    
+    Ok(sheets)
+}
+
+fn read_csv(bytes: &[u8]) -> Result<Vec<Vec<Data>>> {
+    let text = decode_text(bytes);
+    let delimiter = detect_delimiter(&text);
+    
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Erro na linha {} do CSV", i + 1))?;
+        rows.push(
+            record
+                .iter()
+                .map(|field| if field.is_empty() { Data::Empty } else { Data::String(field.to_string()) })
+                .collect(),
+        );
+    }
+    
+    Ok(rows)
+}
+
+// UTF-8 (com ou sem BOM), UTF-16 com BOM ou, na falta deles, Windows-1252/Latin-1,
+// comum em exportações de sistemas brasileiros
+fn decode_text(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return text.into_owned();
+    }
+    
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => {
+            let (text, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes);
+            text.into_owned()
+        }
+    }
+}
+
+// Escolhe o separador mais frequente no cabeçalho, ignorando trechos entre aspas
+fn detect_delimiter(text: &str) -> u8 {
+    let header = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    
+    let mut counts = [0usize; CSV_DELIMITERS.len()];
+    let mut in_quotes = false;
+    for byte in header.bytes() {
+        if byte == b'"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(i) = CSV_DELIMITERS.iter().position(|delimiter| *delimiter == byte) {
+                counts[i] += 1;
+            }
+        }
+    }
+    
+    // Em empate vence o primeiro da lista; sem nenhum separador, a linha é uma única coluna
+    let mut best = 0;
+    for i in 1..CSV_DELIMITERS.len() {
+        if counts[i] > counts[best] {
+            best = i;
+        }
+    }
+    CSV_DELIMITERS[best]
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn detects_format_by_signature() {
+        let mut xls = OLE_SIGNATURE.to_vec();
+        xls.extend_from_slice(&[0; 16]);
+        assert_eq!(SourceFormat::detect(&xls), SourceFormat::Xls);
+        
+        let mut ods = ZIP_SIGNATURE.to_vec();
+        ods.extend_from_slice(b"\0\0mimetypeapplication/vnd.oasis.opendocument.spreadsheet");
+        assert_eq!(SourceFormat::detect(&ods), SourceFormat::Ods);
+        
+        let mut xlsb = ZIP_SIGNATURE.to_vec();
+        xlsb.extend_from_slice(b"\0\0[Content_Types].xml ... xl/workbook.bin");
+        assert_eq!(SourceFormat::detect(&xlsb), SourceFormat::Xlsb);
+        
+        let mut xlsx = ZIP_SIGNATURE.to_vec();
+        xlsx.extend_from_slice(b"\0\0[Content_Types].xml ... xl/workbook.xml");
+        assert_eq!(SourceFormat::detect(&xlsx), SourceFormat::Xlsx);
+        
+        assert_eq!(SourceFormat::detect(b"Nome;Numero\nAna;11999998888\n"), SourceFormat::Csv);
+    }
+    
+    #[test]
+    fn picks_most_frequent_delimiter_outside_quotes() {
+        assert_eq!(detect_delimiter("Nome;Numero;Email\nAna;1;a@b"), b';');
+        assert_eq!(detect_delimiter("Nome,Numero,Email\nAna,1,a@b"), b',');
+        // As vírgulas dentro das aspas não contam
+        assert_eq!(detect_delimiter("\"Silva, Ana\";\"Rua A, 10, apto 2\";Numero"), b';');
+        assert_eq!(detect_delimiter("\"Nome; completo\",Numero"), b',');
+    }
+    
+    #[test]
+    fn decodes_windows_1252_when_not_utf8() {
+        // "ação" em Windows-1252: a=0x61, ç=0xE7, ã=0xE3, o=0x6F
+        assert_eq!(decode_text(&[0x61, 0xE7, 0xE3, 0x6F]), "ação");
+        assert_eq!(decode_text("ação".as_bytes()), "ação");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFNome"), "Nome");
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::time::{sleep, Instant};
+
+use crate::whatsapp_automation::BrowserKind;
+
+// Tempo máximo para o driver começar a aceitar conexões
+const STARTUP_TIMEOUT_SECONDS: u64 = 20;
+
+// Processo do geckodriver/chromedriver iniciado e encerrado pelo próprio programa
+pub struct DriverProcess {
+    child: Child,
+    name: &'static str,
+    url: String,
+}
+
+impl DriverProcess {
+    pub fn binary_name(kind: BrowserKind) -> &'static str {
+        match kind {
+            BrowserKind::Firefox => "geckodriver",
+            BrowserKind::Chrome => "chromedriver",
+        }
+    }
+    
+    // Inicia o driver em uma porta livre e aguarda até que ele esteja pronto
+    pub async fn start(kind: BrowserKind, configured_path: &str) -> Result<Self> {
+        let name = Self::binary_name(kind);
+        let binary = Self::locate(name, configured_path)?;
+        let port = Self::free_port()?;
+        
+        let mut command = Command::new(&binary);
+        match kind {
+            BrowserKind::Firefox => command.arg("--host").arg("127.0.0.1").arg("--port").arg(port.to_string()),
+            BrowserKind::Chrome => command.arg(format!("--port={}", port)),
+        };
+        
+        // Garante que o driver não sobreviva ao programa se a thread de envio terminar sem `stop`
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Falha ao iniciar o {}: {}", name, binary.display()))?;
+        
+        log::info!("{} iniciado ({}) na porta {}", name, binary.display(), port);
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(Self::forward_output(name, stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(Self::forward_output(name, stderr));
+        }
+        
+        let mut process = Self {
+            child,
+            name,
+            url: format!("http://127.0.0.1:{}", port),
+        };
+        
+        if let Err(e) = process.wait_until_ready().await {
+            let _ = process.stop().await;
+            return Err(e);
+        }
+        
+        Ok(process)
+    }
+    
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+    
+    pub async fn stop(&mut self) -> Result<()> {
+        if self.child.try_wait()?.is_none() {
+            self.child.kill().await
+                .with_context(|| format!("Falha ao encerrar o {}", self.name))?;
+            log::info!("{} encerrado", self.name);
+        }
+        Ok(())
+    }
+    
+    // Caminho configurado ou, se vazio, o executável encontrado no PATH
+    fn locate(name: &str, configured_path: &str) -> Result<PathBuf> {
+        let configured_path = configured_path.trim();
+        if !configured_path.is_empty() {
+            let path = PathBuf::from(configured_path);
+            if !path.is_file() {
+                return Err(anyhow::anyhow!("O {} não foi encontrado em {}", name, path.display()));
+            }
+            return Ok(path);
+        }
+        
+        let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+        std::env::var_os("PATH")
+            .and_then(|paths| {
+                std::env::split_paths(&paths)
+                    .map(|dir| dir.join(&file_name))
+                    .find(|path| path.is_file())
+            })
+            .ok_or_else(|| anyhow::anyhow!(
+                "O {} não foi encontrado no PATH. Instale-o ou informe o caminho do executável nas configurações.",
+                name
+            ))
+    }
+    
+    // Reserva uma porta livre pedindo ao sistema a porta 0
+    fn free_port() -> Result<u16> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .context("Não foi possível encontrar uma porta livre para o WebDriver")?;
+        Ok(listener.local_addr()?.port())
+    }
+    
+    // Consulta o endpoint /status até o driver responder
+    async fn wait_until_ready(&mut self) -> Result<()> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()
+            .context("Falha ao criar o cliente HTTP")?;
+        let status_url = format!("{}/status", self.url);
+        let deadline = Instant::now() + Duration::from_secs(STARTUP_TIMEOUT_SECONDS);
+        
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(anyhow::anyhow!(
+                    "O {} terminou durante a inicialização ({}). Veja o log para detalhes.",
+                    self.name,
+                    status
+                ));
+            }
+            
+            if let Ok(response) = client.get(&status_url).send().await {
+                if response.status().is_success() {
+                    return Ok(());
+                }
+            }
+            
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "O {} não respondeu em {} segundos",
+                    self.name,
+                    STARTUP_TIMEOUT_SECONDS
+                ));
+            }
+            
+            sleep(Duration::from_millis(250)).await;
+        }
+    }
+    
+    // Repassa cada linha da saída do driver para o log do programa
+    async fn forward_output(name: &'static str, output: impl AsyncRead + Unpin + Send + 'static) {
+        let mut lines = BufReader::new(output).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log::info!(target: "webdriver", "[{}] {}", name, line);
+        }
+    }
+}
@@ -1,28 +1,235 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use thirtyfour::{By, DesiredCapabilities, WebDriver};
-use tokio::time::sleep;
+use thirtyfour::common::capabilities::firefox::FirefoxPreferences;
+use thirtyfour::{By, Capabilities, DesiredCapabilities, Key, WebDriver, WebElement};
+use tokio::time::{sleep, Instant};
+
+use crate::attachment::Attachment;
+use crate::health_check::{AvailabilityReport, CheckStatus};
+use crate::message_transport::{DeliveryStatus, MessageTransport, QrCodeSink, SendReceipt};
+use crate::selector_profile::SelectorProfile;
+use crate::send_error::{SendError, SendErrorKind};
+use crate::webdriver_process::DriverProcess;
+
+// Tempo máximo para a conversa abrir após navegar para o link do número
+const CHAT_TIMEOUT_SECONDS: u64 = 30;
+
+// Intervalo entre as verificações de login (e capturas do código QR)
+const LOGIN_POLL_SECONDS: u64 = 1;
+
+// Tempo para a verificação prévia aguardar a restauração de uma sessão salva
+const SESSION_CHECK_SECONDS: u64 = 15;
+
+// Tempo máximo aguardando a mensagem sair do estado "pendente" (relógio)
+const CONFIRMATION_TIMEOUT_SECONDS: u64 = 20;
+
+// Acima deste tamanho o WhatsApp Web corta o texto do link; a mensagem é digitada no campo
+const MAX_DEEP_LINK_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BrowserKind {
+    Firefox,
+    // Chrome ou Chromium, controlados pelo chromedriver
+    Chrome,
+}
+
+impl BrowserKind {
+    pub fn all() -> [BrowserKind; 2] {
+        [BrowserKind::Firefox, BrowserKind::Chrome]
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            BrowserKind::Firefox => "Firefox (geckodriver)",
+            BrowserKind::Chrome => "Chrome / Chromium (chromedriver)",
+        }
+    }
+}
+
+// Navegador e servidor WebDriver usados pelo backend do WhatsApp Web
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrowserConfig {
+    pub kind: BrowserKind,
+    // Inicia e encerra o geckodriver/chromedriver automaticamente
+    pub manage_driver: bool,
+    // Executável do driver; vazio procura no PATH
+    pub driver_path: String,
+    // Usada apenas quando o driver é iniciado manualmente
+    pub webdriver_url: String,
+    pub whatsapp_web_url: String,
+    // Executável do navegador; vazio usa o padrão do driver (ex.: informe o Chromium aqui)
+    pub binary_path: String,
+    // Sem janela, para servidores Linux sem interface gráfica
+    pub headless: bool,
+    pub window_width: u32,
+    pub window_height: u32,
+    // Idioma preferido enviado aos sites (Accept-Language), ex.: pt-BR
+    pub language: String,
+    // Argumentos adicionais de linha de comando, separados por espaço
+    pub extra_args: String,
+}
+
+impl Default for BrowserConfig {
+    fn default() -> Self {
+        Self {
+            kind: BrowserKind::Firefox,
+            manage_driver: true,
+            driver_path: String::new(),
+            webdriver_url: String::from("http://localhost:4444"),
+            whatsapp_web_url: String::from("https://web.whatsapp.com/"),
+            binary_path: String::new(),
+            headless: false,
+            window_width: 1280,
+            window_height: 900,
+            language: String::from("pt-BR"),
+            extra_args: String::new(),
+        }
+    }
+}
+
+impl BrowserConfig {
+    pub fn get_extra_args(&self) -> Vec<String> {
+        self.extra_args.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+    
+    // Monta as capacidades do navegador escolhido, usando `profile_dir` como perfil persistente
+    pub fn build_capabilities(&self, profile_dir: Option<&Path>) -> Result<Capabilities> {
+        let binary_path = self.binary_path.trim();
+        let language = self.language.trim();
+        
+        match self.kind {
+            BrowserKind::Firefox => {
+                let mut caps = DesiredCapabilities::firefox();
+                
+                // Reutilizar o perfil salvo para não precisar escanear o código QR a cada execução
+                if let Some(profile_dir) = profile_dir {
+                    caps.add_firefox_arg("-profile")?;
+                    caps.add_firefox_arg(&profile_dir.to_string_lossy())?;
+                }
+                if self.headless {
+                    caps.add_firefox_arg("-headless")?;
+                }
+                caps.add_firefox_arg(&format!("--width={}", self.window_width))?;
+                caps.add_firefox_arg(&format!("--height={}", self.window_height))?;
+                for arg in self.get_extra_args() {
+                    caps.add_firefox_arg(&arg)?;
+                }
+                
+                if !binary_path.is_empty() {
+                    caps.set_firefox_binary(Path::new(binary_path))?;
+                }
+                if !language.is_empty() {
+                    let mut preferences = FirefoxPreferences::new();
+                    preferences.set("intl.accept_languages", language)?;
+                    caps.set_preferences(preferences)?;
+                }
+                
+                Ok(caps.into())
+            }
+            BrowserKind::Chrome => {
+                let mut caps = DesiredCapabilities::chrome();
+                
+                if let Some(profile_dir) = profile_dir {
+                    caps.add_chrome_arg(&format!("--user-data-dir={}", profile_dir.display()))?;
+                }
+                if self.headless {
+                    caps.add_chrome_arg("--headless=new")?;
+                    // Necessários quando o Chrome roda como root ou em contêineres
+                    caps.add_chrome_arg("--no-sandbox")?;
+                    caps.add_chrome_arg("--disable-dev-shm-usage")?;
+                }
+                caps.add_chrome_arg(&format!("--window-size={},{}", self.window_width, self.window_height))?;
+                for arg in self.get_extra_args() {
+                    caps.add_chrome_arg(&arg)?;
+                }
+                
+                if !binary_path.is_empty() {
+                    caps.set_binary(binary_path)?;
+                }
+                if !language.is_empty() {
+                    caps.add_chrome_arg(&format!("--lang={}", language))?;
+                    caps.add_chrome_option("prefs", json!({ "intl.accept_languages": language }))?;
+                }
+                
+                Ok(caps.into())
+            }
+        }
+    }
+}
 
 pub struct WhatsAppAutomation {
     driver: Option<WebDriver>,
+    driver_process: Option<DriverProcess>,
     is_logged_in: bool,
+    selectors: SelectorProfile,
+    browser: BrowserConfig,
+    // Perfil persistente do navegador; mantém a sessão do WhatsApp entre execuções
+    profile_dir: Option<PathBuf>,
 }
 
 impl WhatsAppAutomation {
     pub fn new() -> Self {
         Self {
             driver: None,
+            driver_process: None,
             is_logged_in: false,
+            selectors: SelectorProfile::builtin("pt-BR").expect("perfil de seletores embutido inválido"),
+            browser: BrowserConfig::default(),
+            profile_dir: None,
         }
     }
     
+    pub fn with_profile_dir(mut self, profile_dir: Option<PathBuf>) -> Self {
+        self.profile_dir = profile_dir;
+        self
+    }
+    
+    pub fn with_selector_profile(mut self, selectors: SelectorProfile) -> Self {
+        self.selectors = selectors;
+        self
+    }
+    
+    pub fn with_browser_config(mut self, browser: BrowserConfig) -> Self {
+        self.browser = browser;
+        self
+    }
+    
     pub async fn initialize(&mut self) -> Result<()> {
-        // Configurar o Firefox WebDriver
-        let caps = DesiredCapabilities::firefox();
+        if let Some(profile_dir) = &self.profile_dir {
+            fs::create_dir_all(profile_dir)
+                .with_context(|| format!("Erro ao criar o perfil do navegador: {}", profile_dir.display()))?;
+        }
+        let caps = self.browser.build_capabilities(self.profile_dir.as_deref())?;
+        
+        // Iniciar o driver, se gerenciado pelo programa, ou usar o servidor informado
+        let webdriver_url = if self.browser.manage_driver {
+            let process = DriverProcess::start(self.browser.kind, &self.browser.driver_path).await?;
+            let url = process.get_url().to_string();
+            self.driver_process = Some(process);
+            url
+        } else {
+            self.browser.webdriver_url.trim().to_string()
+        };
         
         // Inicializar o WebDriver
-        let driver = WebDriver::new("http://localhost:4444", caps).await
-            .context("Falha ao inicializar o WebDriver. Verifique se o geckodriver está instalado e em execução.")?;
+        let driver = match WebDriver::new(&webdriver_url, caps).await {
+            Ok(driver) => driver,
+            Err(e) => {
+                self.stop_driver_process().await;
+                return Err(anyhow::Error::new(e).context(format!(
+                    "Falha ao inicializar o WebDriver em {}. Verifique se o {} está instalado e em execução.",
+                    webdriver_url,
+                    DriverProcess::binary_name(self.browser.kind)
+                )));
+            }
+        };
         
         self.driver = Some(driver);
         Ok(())
@@ -33,70 +240,459 @@ impl WhatsAppAutomation {
             .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
         
         // Carregar o WhatsApp Web
-        driver.goto("https://web.whatsapp.com/").await
-            .context("Falha ao carregar o WhatsApp Web")?;
+        let url = self.browser.whatsapp_web_url.trim();
+        driver.goto(url).await
+            .with_context(|| format!("Falha ao carregar o WhatsApp Web ({})", url))?;
         
         Ok(())
     }
     
-    pub async fn wait_for_login(&mut self, _timeout_seconds: u64) -> Result<()> {
+    // Aguarda o login até o tempo limite. Enquanto o código QR estiver na tela,
+    // cada nova versão dele é capturada e entregue a `on_qr_code` como PNG.
+    pub async fn wait_for_login(&mut self, timeout_seconds: u64, on_qr_code: &QrCodeSink<'_>) -> Result<()> {
         let driver = self.driver.as_ref()
             .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
         
-        // Esperar até que a página principal do WhatsApp seja carregada
-        // Isso é indicado pela presença do campo de mensagem
-        driver.find(By::XPath("//div[@contenteditable='true'][@data-tab='3']"))
-            .await
-            .context("Tempo esgotado aguardando o login")?;
+        let deadline = Instant::now() + Duration::from_secs(timeout_seconds);
+        let mut last_qr_code: Option<Vec<u8>> = None;
+        
+        loop {
+            // A página principal do WhatsApp carregada indica login concluído
+            // (ou uma sessão restaurada do perfil salvo)
+            let markers = self.find_in_chain(driver, "logged_in_marker", &self.selectors.logged_in_marker).await?;
+            if !markers.is_empty() {
+                break;
+            }
+            
+            // O WhatsApp troca o código QR periodicamente; reenviar apenas quando mudar
+            let canvases = self.find_in_chain(driver, "qr_code", &self.selectors.qr_code).await?;
+            if let Some(canvas) = canvases.first() {
+                if let Ok(png) = canvas.screenshot_as_png().await {
+                    if last_qr_code.as_ref() != Some(&png) {
+                        on_qr_code(png.clone());
+                        last_qr_code = Some(png);
+                    }
+                }
+            }
+            
+            if Instant::now() >= deadline {
+                return Err(SendError::new(
+                    SendErrorKind::Timeout,
+                    format!("Tempo esgotado aguardando o login ({} segundos)", timeout_seconds),
+                )
+                .into());
+            }
+            
+            sleep(Duration::from_secs(LOGIN_POLL_SECONDS)).await;
+        }
         
         self.is_logged_in = true;
         Ok(())
     }
     
-    pub async fn send_message(&self, numero: &str, mensagem: &str) -> Result<()> {
+    // Verifica se a página já mostra a lista de conversas, ou seja, se a sessão está autenticada
+    pub async fn is_session_authenticated(&self) -> Result<bool> {
+        let driver = self.driver.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
+        
+        let markers = self.find_in_chain(driver, "logged_in_marker", &self.selectors.logged_in_marker).await?;
+        Ok(!markers.is_empty())
+    }
+    
+    // Apaga o perfil salvo, encerrando a sessão do WhatsApp Web neste computador.
+    // Na próxima execução será necessário escanear o código QR novamente.
+    pub fn forget_session(profile_dir: &Path) -> Result<()> {
+        if profile_dir.exists() {
+            fs::remove_dir_all(profile_dir)
+                .with_context(|| format!("Erro ao apagar o perfil do navegador: {}", profile_dir.display()))?;
+        }
+        Ok(())
+    }
+    
+    // Envia a mensagem e devolve o estado observado no balão enviado
+    pub async fn send_message(&self, numero: &str, mensagem: &str) -> Result<DeliveryStatus> {
         if !self.is_logged_in {
-            return Err(anyhow::anyhow!("Não está logado no WhatsApp Web"));
+            return Err(SendError::new(SendErrorKind::SessionLost, "Não está logado no WhatsApp Web").into());
         }
         
         let driver = self.driver.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
+            .ok_or_else(|| SendError::new(SendErrorKind::SessionLost, "WebDriver não inicializado"))?;
         
-        // Codificar a mensagem para URL
-        let mensagem_encoded = mensagem.replace(" ", "%20");
+        let mensagem = Self::normalize_line_breaks(mensagem);
+        let url = Self::build_send_url(&self.browser.whatsapp_web_url, numero, Some(&mensagem));
+        let use_deep_link = url.len() <= MAX_DEEP_LINK_LENGTH && Self::can_use_deep_link(&mensagem);
         
-        // Construir a URL do WhatsApp
-        let url = format!("https://web.whatsapp.com/send?phone={}&text={}", numero, mensagem_encoded);
-        
-        // Navegar para a URL
+        // Navegar para a conversa, já com o texto quando o link consegue representá-lo
+        let url = if use_deep_link { url } else { Self::build_send_url(&self.browser.whatsapp_web_url, numero, None) };
         driver.goto(&url).await
-            .context("Falha ao navegar para a página de conversa")?;
+            .map_err(|e| SendError::from_error(e, SendErrorKind::Unknown, "Falha ao navegar para a página de conversa"))?;
+        
+        if !use_deep_link {
+            let composer = self.wait_for_chat(driver, "composer", &self.selectors.composer).await?;
+            self.insert_text(driver, &composer, &mensagem).await?;
+        }
         
-        // Esperar até que a página de conversa seja carregada
-        let send_button = driver.find(By::XPath("//button[@aria-label='Enviar']"))
-            .await
-            .context("Tempo esgotado aguardando a página de conversa")?;
+        // Esperar até que a página de conversa seja carregada (ou o aviso de número inválido)
+        let send_button = self.wait_for_chat(driver, "send_button", &self.selectors.send_button).await?;
         
         // Pequena pausa para garantir que tudo carregou
         sleep(Duration::from_secs(2)).await;
         
+        // Contar os balões enviados antes do clique, para identificar o novo
+        let previous_count = self.count_outgoing_messages(driver).await?;
+        
         // Clicar no botão de enviar
         send_button.click().await
-            .context("Falha ao clicar no botão de enviar")?;
+            .map_err(|e| SendError::from_error(e, SendErrorKind::ElementNotFound, "Falha ao clicar no botão de enviar"))?;
         
-        // Esperar o envio
-        sleep(Duration::from_secs(2)).await;
+        self.wait_for_delivery_status(driver, previous_count).await
+    }
+    
+    // Link de conversa do WhatsApp Web com o texto totalmente codificado (%20 para espaços,
+    // %0A para quebras de linha, UTF-8 para acentos e emoji)
+    pub fn build_send_url(base_url: &str, numero: &str, mensagem: Option<&str>) -> String {
+        let base_url = base_url.trim().trim_end_matches('/');
+        let phone: String = url::form_urlencoded::byte_serialize(numero.as_bytes()).collect();
+        
+        match mensagem {
+            Some(mensagem) => {
+                // byte_serialize usa '+' para espaço e já codifica o '+' literal como %2B
+                let text = url::form_urlencoded::byte_serialize(mensagem.as_bytes())
+                    .collect::<String>()
+                    .replace('+', "%20");
+                format!("{}/send?phone={}&text={}", base_url, phone, text)
+            }
+            None => format!("{}/send?phone={}", base_url, phone),
+        }
+    }
+    
+    // Caracteres de controle (exceto quebra de linha e tabulação) são descartados pelo link
+    fn can_use_deep_link(mensagem: &str) -> bool {
+        !mensagem.chars().any(|c| c.is_control() && c != '\n' && c != '\t')
+    }
+    
+    fn normalize_line_breaks(mensagem: &str) -> String {
+        mensagem.replace("\r\n", "\n").replace('\r', "\n")
+    }
+    
+    // Insere o texto no campo (mensagem ou legenda) linha a linha pelo navegador: ao contrário de
+    // digitar tecla a tecla, preserva emoji (que o chromedriver não consegue digitar). As linhas são
+    // separadas com Shift+Enter, porque Enter sozinho enviaria a mensagem antes da hora.
+    async fn insert_text(&self, driver: &WebDriver, field: &WebElement, text: &str) -> Result<()> {
+        field.click().await
+            .map_err(|e| SendError::from_error(e, SendErrorKind::ElementNotFound, "Falha ao selecionar o campo de texto"))?;
+        
+        for (i, line) in Self::normalize_line_breaks(text).split('\n').enumerate() {
+            if i > 0 {
+                field.send_keys(Key::Shift + Key::Enter).await
+                    .map_err(|e| SendError::from_error(e, SendErrorKind::ElementNotFound, "Falha ao quebrar a linha"))?;
+            }
+            if !line.is_empty() {
+                Self::insert_at_cursor(driver, field, line).await
+                    .map_err(|e| SendError::from_error(e, SendErrorKind::Unknown, "Falha ao inserir o texto"))?;
+            }
+        }
         
         Ok(())
     }
     
-    pub async fn close(&mut self) -> Result<()> {
-        if let Some(driver) = self.driver.take() {
-            driver.quit().await
-                .context("Falha ao fechar o WebDriver")?;
+    // Insere o texto na posição atual do cursor, sem clicar (o clique moveria o cursor)
+    async fn insert_at_cursor(driver: &WebDriver, element: &WebElement, text: &str) -> Result<()> {
+        driver
+            .execute(
+                "arguments[0].focus(); document.execCommand('insertText', false, arguments[1]);",
+                vec![element.to_json()?, json!(text)],
+            )
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Envia um arquivo pelo menu de anexos, com legenda opcional
+    pub async fn send_attachment(&self, numero: &str, file_path: &Path, caption: Option<&str>) -> Result<DeliveryStatus> {
+        if !self.is_logged_in {
+            return Err(SendError::new(SendErrorKind::SessionLost, "Não está logado no WhatsApp Web").into());
+        }
+        
+        let driver = self.driver.as_ref()
+            .ok_or_else(|| SendError::new(SendErrorKind::SessionLost, "WebDriver não inicializado"))?;
+        
+        let attachment = Attachment::from_path(file_path)?;
+        // O navegador precisa do caminho absoluto para o campo de arquivo
+        let absolute_path = fs::canonicalize(&attachment.path)
+            .map_err(|e| SendError::new(SendErrorKind::Attachment, format!("Anexo inacessível: {} ({})", attachment.path.display(), e)))?;
+        
+        let url = Self::build_send_url(&self.browser.whatsapp_web_url, numero, None);
+        driver.goto(&url).await
+            .map_err(|e| SendError::from_error(e, SendErrorKind::Unknown, "Falha ao navegar para a página de conversa"))?;
+        
+        let attach_button = self.wait_for_chat(driver, "attach_button", &self.selectors.attach_button).await?;
+        sleep(Duration::from_secs(2)).await;
+        
+        let previous_count = self.count_outgoing_messages(driver).await?;
+        
+        attach_button.click().await
+            .map_err(|e| SendError::from_error(e, SendErrorKind::ElementNotFound, "Falha ao abrir o menu de anexos"))?;
+        
+        // Fotos e vídeos usam o campo de mídia (com pré-visualização); o restante vai como documento
+        let (element, chain) = if attachment.kind.is_visual_media() {
+            ("media_input", &self.selectors.media_input)
+        } else {
+            ("document_input", &self.selectors.document_input)
+        };
+        let input = self
+            .find_in_chain(driver, element, chain)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| self.not_found_error(element, chain, SendErrorKind::ElementNotFound))?;
+        
+        input.send_keys(absolute_path.to_string_lossy().as_ref()).await
+            .map_err(|e| SendError::from_error(e, SendErrorKind::Attachment, "Falha ao selecionar o arquivo"))?;
+        
+        // A pré-visualização só abre se o WhatsApp aceitar o arquivo
+        let send_button = self.wait_for_attachment_preview(driver, &attachment).await?;
+        
+        if let Some(caption) = caption.filter(|caption| !caption.trim().is_empty()) {
+            self.type_caption(driver, caption).await?;
+        }
+        
+        send_button.click().await
+            .map_err(|e| SendError::from_error(e, SendErrorKind::ElementNotFound, "Falha ao clicar no botão de enviar o anexo"))?;
+        
+        self.wait_for_delivery_status(driver, previous_count).await
+    }
+    
+    async fn wait_for_attachment_preview(&self, driver: &WebDriver, attachment: &Attachment) -> Result<WebElement> {
+        let deadline = Instant::now() + Duration::from_secs(CHAT_TIMEOUT_SECONDS);
+        
+        loop {
+            let buttons = self.find_in_chain(driver, "media_send_button", &self.selectors.media_send_button).await?;
+            if let Some(button) = buttons.into_iter().next() {
+                return Ok(button);
+            }
+            
+            // O WhatsApp recusa o arquivo com um diálogo (ex.: tamanho acima do permitido)
+            if let Some(message) = self.dismiss_popup_matching(driver, &self.selectors.attachment_rejected_texts).await {
+                return Err(SendError::new(
+                    SendErrorKind::Attachment,
+                    format!(
+                        "O WhatsApp recusou o anexo {} ({:.1} MB, {}): {}",
+                        attachment.get_file_name(),
+                        attachment.get_size_megabytes(),
+                        attachment.mime_type,
+                        message
+                    ),
+                )
+                .into());
+            }
+            
+            if Instant::now() >= deadline {
+                return Err(SendError::new(
+                    SendErrorKind::Attachment,
+                    format!(
+                        "A pré-visualização do anexo {} não abriu. Seletores tentados: {} ({})",
+                        attachment.get_file_name(),
+                        self.selectors.media_send_button.join(" | "),
+                        self.selectors.describe()
+                    ),
+                )
+                .into());
+            }
+            
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+    
+    // A legenda usa o mesmo caminho da mensagem, inclusive para as quebras de linha
+    async fn type_caption(&self, driver: &WebDriver, caption: &str) -> Result<()> {
+        let caption_box = self
+            .find_in_chain(driver, "media_caption", &self.selectors.media_caption)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| self.not_found_error("media_caption", &self.selectors.media_caption, SendErrorKind::ElementNotFound))?;
+        
+        self.insert_text(driver, &caption_box, caption).await
+    }
+    
+    // Procura um elemento pela cadeia de seletores do perfil e devolve
+    // os resultados do primeiro seletor que encontrar algo
+    async fn find_in_chain(&self, driver: &WebDriver, element: &str, chain: &[String]) -> Result<Vec<WebElement>> {
+        for selector in chain {
+            let found = driver.find_all(By::XPath(selector.as_str())).await
+                .map_err(|e| self.selector_error(element, selector, e))?;
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+        Ok(Vec::new())
+    }
+    
+    // Igual a find_in_chain, mas com seletores relativos a um elemento já encontrado
+    async fn find_in_element_chain(&self, parent: &WebElement, element: &str, chain: &[String]) -> Result<Vec<WebElement>> {
+        for selector in chain {
+            let found = parent.find_all(By::XPath(selector.as_str())).await
+                .map_err(|e| self.selector_error(element, selector, e))?;
+            if !found.is_empty() {
+                return Ok(found);
+            }
         }
+        Ok(Vec::new())
+    }
+    
+    fn selector_error(&self, element: &str, selector: &str, error: impl std::fmt::Display) -> SendError {
+        SendError::from_error(
+            error,
+            SendErrorKind::Unknown,
+            &format!("Falha no seletor '{}' do elemento '{}' ({})", selector, element, self.selectors.describe()),
+        )
+    }
+    
+    fn not_found_error(&self, element: &str, chain: &[String], kind: SendErrorKind) -> SendError {
+        SendError::new(
+            kind,
+            format!(
+                "Elemento '{}' não encontrado. Seletores tentados: {} ({})",
+                element,
+                chain.join(" | "),
+                self.selectors.describe()
+            ),
+        )
+    }
+    
+    async fn count_outgoing_messages(&self, driver: &WebDriver) -> Result<usize> {
+        let bubbles = self.find_in_chain(driver, "outgoing_message", &self.selectors.outgoing_message).await?;
+        Ok(bubbles.len())
+    }
+    
+    // Aguarda o novo balão aparecer e sair do estado pendente.
+    // Chamado depois do clique em enviar: a mensagem pode já ter saído, então nenhuma falha
+    // aqui vira erro (um erro levaria a uma nova tentativa e a uma mensagem duplicada).
+    // Se o tempo acabar sem confirmação, devolve Pending.
+    async fn wait_for_delivery_status(&self, driver: &WebDriver, previous_count: usize) -> Result<DeliveryStatus> {
+        let deadline = Instant::now() + Duration::from_secs(CONFIRMATION_TIMEOUT_SECONDS);
         
+        loop {
+            match self.find_in_chain(driver, "outgoing_message", &self.selectors.outgoing_message).await {
+                Ok(bubbles) if bubbles.len() > previous_count => {
+                    if let Some(bubble) = bubbles.last() {
+                        let status = self.read_delivery_status(bubble).await;
+                        if status != DeliveryStatus::Pending {
+                            return Ok(status);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    log::warn!("Não foi possível confirmar o envio: {:#}", e);
+                    return Ok(DeliveryStatus::Pending);
+                }
+            }
+            
+            if Instant::now() >= deadline {
+                return Ok(DeliveryStatus::Pending);
+            }
+            
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+    
+    // Lê o ícone de status do balão: relógio (pendente), um tique (enviada) ou dois tiques (entregue)
+    async fn read_delivery_status(&self, bubble: &WebElement) -> DeliveryStatus {
+        let Ok(icons) = self.find_in_element_chain(bubble, "status_icon", &self.selectors.status_icon).await else {
+            return DeliveryStatus::Pending;
+        };
+        
+        for icon in icons {
+            let name = icon.attr("data-icon").await.ok().flatten().unwrap_or_default();
+            if self.selectors.delivered_icons.contains(&name) {
+                return DeliveryStatus::Delivered;
+            }
+            if self.selectors.sent_icons.contains(&name) {
+                return DeliveryStatus::Sent;
+            }
+            if self.selectors.pending_icons.contains(&name) {
+                return DeliveryStatus::Pending;
+            }
+        }
+        
+        DeliveryStatus::Pending
+    }
+    
+    // Aguarda a conversa abrir, indicada pelo elemento informado (`element`, procurado pela
+    // cadeia `chain`). Se o WhatsApp Web informar que o número é inválido, fecha o aviso e
+    // falha imediatamente em vez de esperar o tempo limite.
+    async fn wait_for_chat(&self, driver: &WebDriver, element: &str, chain: &[String]) -> Result<WebElement> {
+        let deadline = Instant::now() + Duration::from_secs(CHAT_TIMEOUT_SECONDS);
+        
+        loop {
+            let buttons = self.find_in_chain(driver, element, chain).await?;
+            if let Some(button) = buttons.into_iter().next() {
+                return Ok(button);
+            }
+            
+            if let Some(message) = self.dismiss_invalid_number_dialog(driver).await {
+                return Err(SendError::new(
+                    SendErrorKind::NotOnWhatsApp,
+                    format!("O número não está no WhatsApp: {}", message),
+                )
+                .into());
+            }
+            
+            if Instant::now() >= deadline {
+                return Err(self.not_found_error(element, chain, SendErrorKind::Timeout).into());
+            }
+            
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+    
+    // Procura o aviso de número inválido; se encontrado, clica em "OK" e devolve o texto do aviso
+    async fn dismiss_invalid_number_dialog(&self, driver: &WebDriver) -> Option<String> {
+        self.dismiss_popup_matching(driver, &self.selectors.invalid_number_texts).await
+    }
+    
+    // Fecha o diálogo cujo texto contém um dos trechos e devolve esse texto
+    async fn dismiss_popup_matching(&self, driver: &WebDriver, patterns: &[String]) -> Option<String> {
+        let popups = self.find_in_chain(driver, "popup", &self.selectors.popup).await.ok()?;
+        
+        for popup in popups {
+            let text = popup.text().await.unwrap_or_default();
+            let lower = text.to_lowercase();
+            
+            // O mesmo modal é usado para "Iniciando conversa...", então é preciso checar o texto
+            if patterns.iter().any(|pattern| lower.contains(pattern.as_str())) {
+                let buttons = self.find_in_element_chain(&popup, "popup_button", &self.selectors.popup_button).await;
+                if let Some(button) = buttons.ok().and_then(|buttons| buttons.into_iter().next()) {
+                    let _ = button.click().await;
+                }
+                return Some(text.replace('\n', " ").trim().to_string());
+            }
+        }
+        
+        None
+    }
+    
+    pub async fn close(&mut self) -> Result<()> {
         self.is_logged_in = false;
-        Ok(())
+        
+        // O driver é encerrado mesmo se o navegador não fechar corretamente
+        let result = match self.driver.take() {
+            Some(driver) => driver.quit().await.context("Falha ao fechar o WebDriver"),
+            None => Ok(()),
+        };
+        self.stop_driver_process().await;
+        
+        result
+    }
+    
+    async fn stop_driver_process(&mut self) {
+        if let Some(mut process) = self.driver_process.take() {
+            if let Err(e) = process.stop().await {
+                log::warn!("{:#}", e);
+            }
+        }
     }
     
     // Função auxiliar para formatar números de telefone
@@ -119,10 +715,215 @@ impl WhatsAppAutomation {
         numero
     }
     
-    // Função para verificar se o WhatsApp Web está disponível
-    pub async fn check_whatsapp_web_availability() -> Result<bool> {
-        // Em uma implementação real, verificaríamos a disponibilidade do WhatsApp Web
-        // Como simplificação, apenas retornamos true
-        Ok(true)
+    // Verifica se o número formatado tem um tamanho plausível (código do país + DDD + número)
+    pub fn is_valid_phone_number(numero: &str) -> bool {
+        let digits = numero.chars().filter(|c| c.is_ascii_digit()).count();
+        (10..=15).contains(&digits)
+    }
+    
+    // Verificação prévia: servidor WebDriver, carregamento da página, login e avisos de desconexão.
+    // O navegador aberto para a verificação é fechado ao final.
+    pub async fn check_whatsapp_web_availability(&mut self) -> AvailabilityReport {
+        const WEBDRIVER: &str = "Servidor WebDriver";
+        const PAGE: &str = "WhatsApp Web";
+        const SESSION: &str = "Sessão";
+        const CONNECTION: &str = "Conexão do celular";
+        
+        let mut report = AvailabilityReport::new();
+        
+        match self.initialize().await {
+            Ok(()) => {
+                let detail = if self.browser.manage_driver {
+                    format!("{} iniciado automaticamente", DriverProcess::binary_name(self.browser.kind))
+                } else {
+                    format!("Conectado a {}", self.browser.webdriver_url.trim())
+                };
+                report.add(WEBDRIVER, CheckStatus::Passed, detail);
+            }
+            Err(e) => {
+                report.add(WEBDRIVER, CheckStatus::Failed, format!("{:#}", e));
+                report.skip(&[PAGE, SESSION, CONNECTION]);
+                let _ = self.close().await;
+                return report;
+            }
+        }
+        
+        match self.load_whatsapp_web().await {
+            Ok(()) => report.add(PAGE, CheckStatus::Passed, format!("{} carregado", self.browser.whatsapp_web_url.trim())),
+            Err(e) => {
+                report.add(PAGE, CheckStatus::Failed, format!("{:#}", e));
+                report.skip(&[SESSION, CONNECTION]);
+                let _ = self.close().await;
+                return report;
+            }
+        }
+        
+        match self.wait_for_saved_session().await {
+            Ok(true) => {
+                report.add(SESSION, CheckStatus::Passed, "Conectada");
+                match self.find_disconnected_banner().await {
+                    Ok(None) => report.add(CONNECTION, CheckStatus::Passed, "Nenhum aviso de desconexão"),
+                    Ok(Some(banner)) => report.add(CONNECTION, CheckStatus::Failed, banner),
+                    Err(e) => report.add(CONNECTION, CheckStatus::Failed, format!("{:#}", e)),
+                }
+            }
+            Ok(false) => {
+                report.add(SESSION, CheckStatus::Warning, "Não conectada; será preciso escanear o código QR");
+                report.skip(&[CONNECTION]);
+            }
+            Err(e) => {
+                report.add(SESSION, CheckStatus::Failed, format!("{:#}", e));
+                report.skip(&[CONNECTION]);
+            }
+        }
+        
+        let _ = self.close().await;
+        report
+    }
+    
+    // Aguarda a restauração da sessão salva; desiste assim que o código QR aparece
+    async fn wait_for_saved_session(&self) -> Result<bool> {
+        let driver = self.driver.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
+        
+        let deadline = Instant::now() + Duration::from_secs(SESSION_CHECK_SECONDS);
+        while Instant::now() < deadline {
+            if self.is_session_authenticated().await? {
+                return Ok(true);
+            }
+            if !self.find_in_chain(driver, "qr_code", &self.selectors.qr_code).await?.is_empty() {
+                return Ok(false);
+            }
+            sleep(Duration::from_secs(LOGIN_POLL_SECONDS)).await;
+        }
+        
+        Ok(false)
+    }
+    
+    // Texto do aviso de "celular/computador não conectado", se estiver na tela
+    async fn find_disconnected_banner(&self) -> Result<Option<String>> {
+        let driver = self.driver.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("WebDriver não inicializado"))?;
+        
+        let banners = self.find_in_chain(driver, "disconnected_banner", &self.selectors.disconnected_banner).await?;
+        match banners.first() {
+            Some(banner) => Ok(Some(banner.text().await.unwrap_or_default())),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageTransport for WhatsAppAutomation {
+    fn name(&self) -> &str {
+        "WhatsApp Web"
+    }
+    
+    async fn connect(&mut self) -> Result<()> {
+        self.initialize().await?;
+        self.load_whatsapp_web().await
+    }
+    
+    async fn ensure_logged_in(&mut self, timeout_seconds: u64, on_qr_code: &QrCodeSink<'_>) -> Result<()> {
+        self.wait_for_login(timeout_seconds, on_qr_code).await
+    }
+    
+    async fn send_text(
+        &mut self,
+        numero: &str,
+        mensagem: &str,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let delivery_status = self.send_message(numero, mensagem).await?;
+        Ok(SendReceipt {
+            delivery_status: Some(delivery_status),
+            ..Default::default()
+        })
+    }
+    
+    async fn send_media(
+        &mut self,
+        numero: &str,
+        file_path: &Path,
+        caption: Option<&str>,
+        _contact_data: &HashMap<String, String>,
+    ) -> Result<SendReceipt> {
+        let delivery_status = self.send_attachment(numero, file_path, caption).await?;
+        Ok(SendReceipt {
+            delivery_status: Some(delivery_status),
+            ..Default::default()
+        })
+    }
+    
+    async fn close(&mut self) -> Result<()> {
+        WhatsAppAutomation::close(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const BASE_URL: &str = "https://web.whatsapp.com/";
+    
+    // Decodifica o parâmetro `text` como o WhatsApp Web faria
+    fn decoded_text(link: &str) -> Option<String> {
+        let parsed = url::Url::parse(link).expect("link inválido");
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == "text")
+            .map(|(_, value)| value.into_owned())
+    }
+    
+    fn assert_round_trip(mensagem: &str) {
+        let link = WhatsAppAutomation::build_send_url(BASE_URL, "+5511999998888", Some(mensagem));
+        assert_eq!(decoded_text(&link).as_deref(), Some(mensagem), "link: {}", link);
+    }
+    
+    #[test]
+    fn round_trips_accents_and_cedilla() {
+        assert_round_trip("Olá João, sua inscrição está confirmada! Atenção: ação até às 18h.");
+        assert_round_trip("ÁÉÍÓÚ ÂÊÔ ÃÕ À Ç áéíóú âêô ãõ à ç ü");
+    }
+    
+    #[test]
+    fn round_trips_url_reserved_characters() {
+        assert_round_trip("Preço: R$ 10 + 5% = R$ 10,50 & frete #grátis? sim/não; a=b");
+        assert_round_trip("100% garantido: https://exemplo.com.br/promo?cupom=ABC&ref=zap#topo");
+        assert_round_trip("Use + ou %20 ou %2B literalmente");
+    }
+    
+    #[test]
+    fn round_trips_line_breaks_and_tabs() {
+        assert_round_trip("Olá Maria,\n\nSegue o boleto.\n\tValor: R$ 99,90\nAtenciosamente");
+    }
+    
+    #[test]
+    fn round_trips_emoji() {
+        assert_round_trip("Parabéns! 🎉🥳 Feliz aniversário 🎂");
+        assert_round_trip("Família 👨‍👩‍👧‍👦, bandeira 🇧🇷, pele 👍🏽 e coração ❤️");
+    }
+    
+    #[test]
+    fn encodes_spaces_as_percent_20() {
+        let link = WhatsAppAutomation::build_send_url(BASE_URL, "5511999998888", Some("a b+c"));
+        assert_eq!(link, "https://web.whatsapp.com/send?phone=5511999998888&text=a%20b%2Bc");
+    }
+    
+    #[test]
+    fn encodes_phone_plus_sign() {
+        let link = WhatsAppAutomation::build_send_url(BASE_URL, "+5511999998888", None);
+        assert_eq!(link, "https://web.whatsapp.com/send?phone=%2B5511999998888");
+    }
+    
+    #[test]
+    fn normalizes_windows_line_breaks() {
+        assert_eq!(WhatsAppAutomation::normalize_line_breaks("a\r\nb\rc"), "a\nb\nc");
+    }
+    
+    #[test]
+    fn rejects_control_characters_for_deep_link() {
+        assert!(WhatsAppAutomation::can_use_deep_link("linha 1\nlinha 2\tfim"));
+        assert!(!WhatsAppAutomation::can_use_deep_link("sino\u{7}"));
     }
 }